@@ -0,0 +1,69 @@
+use std::path::{Path, PathBuf};
+
+use crate::{statements, try_eval, try_parse, ArithError, EvalOptions, AST};
+
+const EXTENSION: &str = "arith";
+
+pub struct FileResult {
+    pub path: PathBuf,
+    pub source: String,
+    pub outcome: Result<AST, ArithError>,
+}
+
+pub struct BatchReport {
+    pub results: Vec<FileResult>,
+}
+
+impl BatchReport {
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|r| r.outcome.is_ok()).count()
+    }
+
+    pub fn errored(&self) -> usize {
+        self.results.iter().filter(|r| r.outcome.is_err()).count()
+    }
+}
+
+/// Evaluates each `;`-separated statement of `source` in turn, as the language server reads
+/// a file, up to the first error. A file passes with the value of its last statement.
+fn eval_file(source: &str, options: &EvalOptions) -> Result<AST, ArithError> {
+    let eval = |src: &str| try_parse(src).and_then(|v| try_eval(v, options));
+    let mut last = None;
+    for (_, statement) in statements(source) {
+        last = Some(eval(&statement)?);
+    }
+    // A file with no statements fails to parse, just as an empty term does.
+    last.map_or_else(|| eval(source), Ok)
+}
+
+/// Evaluates every `.arith` file directly inside `dir` under `options`, in file name order.
+/// Files that cannot be read are reported as errors rather than aborting the run.
+pub fn run_batch(dir: &Path, options: &EvalOptions) -> Result<BatchReport, ArithError> {
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == EXTENSION) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    let results = paths
+        .into_iter()
+        .map(|path| match std::fs::read_to_string(&path) {
+            Ok(source) => {
+                let outcome = eval_file(&source, options);
+                FileResult {
+                    path,
+                    source,
+                    outcome,
+                }
+            }
+            Err(e) => FileResult {
+                path,
+                source: String::new(),
+                outcome: Err(e.into()),
+            },
+        })
+        .collect();
+    Ok(BatchReport { results })
+}
@@ -0,0 +1,285 @@
+//! The `arith` command line, exposed so that other drivers can embed it.
+
+use std::{
+    fmt::Write,
+    io::IsTerminal,
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use tapl_diagnostics::{ColorChoice, Renderer, SourceFile};
+
+use crate::{
+    arith_depth, arith_size,
+    batch::run_batch,
+    eval_ast_traced, eval_ast_with, eval_small_traced, eval_small_with,
+    explain::derive_with,
+    gen::{bounded_term, Gen},
+    output::{render, render_ascii, Format},
+    pretty::pretty,
+    trace::{steps_json, tapl_trace},
+    try_parse, ArithError, EvalOptions, AST,
+};
+
+#[derive(Default)]
+pub enum Command {
+    #[default]
+    Stdin,
+    Batch(PathBuf),
+    Gen {
+        seed: u64,
+        size: usize,
+        max_depth: u128,
+    },
+}
+
+#[derive(Default)]
+pub struct Options {
+    pub command: Command,
+    /// Evaluate this term instead of a line read from stdin.
+    pub expr: Option<String>,
+    pub output: Format,
+    pub width: Option<usize>,
+    pub explain_rule: bool,
+    /// Evaluate one E-rule step at a time instead of by the B-rules.
+    pub small_step: bool,
+    pub eval: EvalOptions,
+    pub ascii_only: bool,
+    pub color: ColorChoice,
+    /// Print the evaluation as a `tapl-trace` JSON document instead.
+    pub tapl_trace: bool,
+    /// Print each step of the evaluation as a `{step, rule, term}` JSON record instead.
+    pub steps_json: bool,
+}
+
+impl Options {
+    /// Reads every flag first and only then checks them against the command, so the order
+    /// of the arguments never matters; a flag the command would ignore is rejected.
+    pub fn parse(args: impl Iterator<Item = String>) -> Option<Options> {
+        let mut options = Options::default();
+        let mut args = args.peekable();
+        let gen = args.next_if(|arg| arg == "gen").is_some();
+        let (mut batch, mut seed, mut size, mut max_depth) = (None, None, None, None);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--batch" => batch = Some(PathBuf::from(args.next()?)),
+                "--seed" => seed = Some(args.next()?.parse().ok()?),
+                "--size" => size = Some(args.next()?.parse().ok().filter(|&n| n > 0)?),
+                "--max-depth" => max_depth = Some(args.next()?.parse().ok()?),
+                "--expr" => options.expr = Some(args.next()?),
+                "--explain-rule" => options.explain_rule = true,
+                "--small-step" => options.small_step = true,
+                "--emit-steps-json" => options.steps_json = true,
+                "--strict-numerals" => options.eval.strict_numerals = true,
+                "--trace-format" => {
+                    options.tapl_trace = args.next()? == "tapl";
+                    if !options.tapl_trace {
+                        return None;
+                    }
+                }
+                "--modulus" => {
+                    options.eval.modulus = Some(args.next()?.parse().ok().filter(|&m| m > 0)?)
+                }
+                "--ascii-only" => options.ascii_only = true,
+                "--output" => options.output = args.next()?.parse().ok()?,
+                "--width" => options.width = Some(args.next()?.parse().ok()?),
+                "--color" => options.color = args.next()?.parse().ok()?,
+                _ => return None,
+            }
+        }
+        // Only a single term has anything to explain, step through or trace.
+        let single_term = options.expr.is_some()
+            || options.explain_rule
+            || options.small_step
+            || options.steps_json
+            || options.tapl_trace;
+        let evaluates = options.eval != EvalOptions::default();
+        let generates = seed.is_some() || size.is_some() || max_depth.is_some();
+        options.command = match (gen, batch) {
+            (true, None) if !single_term && !evaluates => Command::Gen {
+                seed: seed.unwrap_or(0),
+                size: size.unwrap_or(10),
+                max_depth: max_depth.unwrap_or(u128::MAX),
+            },
+            (false, Some(dir)) if !single_term && !generates => Command::Batch(dir),
+            (false, None) if !generates => Command::Stdin,
+            _ => return None,
+        };
+        // Traces record the B-rules, which a small-step run never uses.
+        if options.small_step && options.tapl_trace {
+            return None;
+        }
+        if options.tapl_trace && options.steps_json {
+            return None;
+        }
+        Some(options)
+    }
+
+    fn show(&self, v: &AST) -> String {
+        match (self.output, self.width) {
+            (Format::Text, Some(width)) => pretty(v, width),
+            (format, _) if self.ascii_only => render_ascii(v, format),
+            (format, _) => render(v, format),
+        }
+    }
+}
+
+fn labelled(label: &str, text: String) -> String {
+    if text.contains('\n') {
+        format!("{}:\n{}", label, text)
+    } else {
+        format!("{}: {}", label, text)
+    }
+}
+
+// Writes what evaluating `input` prints to `out`, leaving any error to the caller.
+fn run_input(options: &Options, input: &str, out: &mut String) -> Result<(), ArithError> {
+    let input = try_parse(input)?;
+    if options.tapl_trace {
+        let trace = tapl_trace(input.clone(), &options.eval);
+        writeln!(out, "{}", trace.to_json()).unwrap();
+        // Render the diagnostic too, if evaluation got stuck.
+        return eval_ast_with(input, &options.eval).map(|_| ());
+    }
+    if options.steps_json {
+        let (steps, result) = if options.small_step {
+            let (steps, result) = eval_small_traced(input, &options.eval);
+            let steps = steps.into_iter().map(|s| (s.rule.name(), s.term));
+            (steps.collect::<Vec<_>>(), result)
+        } else {
+            let (steps, result) = eval_ast_traced(input, &options.eval);
+            let steps = steps.into_iter().map(|s| (s.rule.name(), s.term));
+            (steps.collect(), result)
+        };
+        writeln!(out, "{}", steps_json(&steps)).unwrap();
+        return result.map(|_| ());
+    }
+    writeln!(out, "{}", labelled("Input", options.show(&input))).unwrap();
+    writeln!(
+        out,
+        "Depth: {}, Size: {}",
+        arith_depth(&input),
+        arith_size(&input)
+    )
+    .unwrap();
+    let output = if options.small_step && options.explain_rule {
+        let (steps, result) = eval_small_traced(input.clone(), &options.eval);
+        let arrow = if options.ascii_only { "->" } else { "→" };
+        let mut before = input;
+        for step in steps {
+            writeln!(out, "{}: {} {} {}", step.rule, before, arrow, step.term).unwrap();
+            before = step.term;
+        }
+        result?
+    } else if options.small_step {
+        eval_small_with(input, &options.eval)?
+    } else if options.explain_rule {
+        let derivation = derive_with(input, &options.eval)?;
+        let arrow = if options.ascii_only { "=>" } else { "⇓" };
+        for step in derivation.steps() {
+            writeln!(out, "{}: {} {} {}", step.rule, step.term, arrow, step.value).unwrap();
+        }
+        derivation.value
+    } else {
+        eval_ast_with(input, &options.eval)?
+    };
+    writeln!(out, "{}", labelled("Output", options.show(&output))).unwrap();
+    Ok(())
+}
+
+fn run_batch_dir(options: &Options, dir: &Path) -> Result<(), ArithError> {
+    let report = run_batch(dir, &options.eval)?;
+    for result in &report.results {
+        match &result.outcome {
+            Ok(output) => println!(
+                "{}: {}",
+                result.path.display(),
+                labelled("Output", options.show(output))
+            ),
+            Err(e) => {
+                let file = SourceFile::new(result.path.display().to_string(), &*result.source);
+                println!(
+                    "{}",
+                    options
+                        .color
+                        .renderer(std::io::stdout().is_terminal())
+                        .render(&e.to_diagnostic(), &file)
+                );
+            }
+        }
+    }
+    println!("{} passed, {} errored", report.passed(), report.errored());
+    Ok(())
+}
+
+pub const USAGE: &str =
+    "Usage: arith [--expr <term>] [--explain-rule] [--strict-numerals] [--modulus <n>]
+             [--trace-format tapl | --emit-steps-json] [<output options>]
+       arith --small-step [--expr <term>] [--explain-rule | --emit-steps-json]
+             [--strict-numerals] [--modulus <n>] [<output options>]
+       arith --batch <dir> [--strict-numerals] [--modulus <n>] [<output options>]
+       arith gen [--seed <n>] [--size <n>] [--max-depth <n>] [<output options>]
+
+Output options: --output <format>, --width <columns>, --ascii-only, --color <when>
+Formats: text (default), json, sexpr, dot, tree";
+
+/// Evaluates `input` as `arith --expr` would, returning everything it prints: its output,
+/// then any error, rendered without color against a file called `name`.
+pub fn transcript(options: &Options, name: &str, input: &str) -> String {
+    let mut out = String::new();
+    if let Err(e) = run_input(options, input, &mut out) {
+        let file = SourceFile::new(name, input);
+        writeln!(
+            out,
+            "{}",
+            Renderer::plain().render(&e.to_diagnostic(), &file)
+        )
+        .unwrap();
+    }
+    out
+}
+
+/// Runs the command selected by `options`, rendering any error to stderr. Everything
+/// happens inside `with_stack`, so any term the parser accepts can be run.
+pub fn run(options: &Options) -> ExitCode {
+    crate::with_stack(|| run_here(options))
+}
+
+fn run_here(options: &Options) -> ExitCode {
+    let (result, file) = match &options.command {
+        Command::Stdin => {
+            let (name, input) = match &options.expr {
+                Some(expr) => ("<expr>", expr.clone()),
+                None => {
+                    let mut buf = String::new();
+                    std::io::stdin()
+                        .read_line(&mut buf)
+                        .expect("Failed to read input");
+                    ("<stdin>", buf.trim_end().to_owned())
+                }
+            };
+            let mut out = String::new();
+            let result = run_input(options, &input, &mut out);
+            print!("{}", out);
+            (result, SourceFile::new(name, input))
+        }
+        Command::Batch(dir) => (run_batch_dir(options, dir), SourceFile::new("", "")),
+        Command::Gen {
+            seed,
+            size,
+            max_depth,
+        } => {
+            let program = bounded_term(&mut Gen::new(*seed, *size), *max_depth);
+            println!("{}", options.show(&program));
+            (Ok(()), SourceFile::new("", ""))
+        }
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            let renderer = options.color.renderer(std::io::stderr().is_terminal());
+            eprintln!("{}", renderer.render(&e.to_diagnostic(), &file));
+            ExitCode::FAILURE
+        }
+    }
+}
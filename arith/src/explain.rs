@@ -0,0 +1,257 @@
+use crate::{is_numeric_val, is_val, ArithError, EvalOptions, AST};
+
+/// The big-step evaluation rules of TAPL exercise 3.5.17.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EvalRule {
+    Value,
+    IfTrue,
+    IfFalse,
+    Succ,
+    PredZero,
+    PredSucc,
+    IsZeroZero,
+    IsZeroSucc,
+}
+
+impl EvalRule {
+    pub fn name(self) -> &'static str {
+        match self {
+            EvalRule::Value => "B-Value",
+            EvalRule::IfTrue => "B-IfTrue",
+            EvalRule::IfFalse => "B-IfFalse",
+            EvalRule::Succ => "B-Succ",
+            EvalRule::PredZero => "B-PredZero",
+            EvalRule::PredSucc => "B-PredSucc",
+            EvalRule::IsZeroZero => "B-IsZeroZero",
+            EvalRule::IsZeroSucc => "B-IsZeroSucc",
+        }
+    }
+}
+
+impl std::fmt::Display for EvalRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The axioms of the small-step relation in TAPL figures 3-1 and 3-2. The congruence
+/// rules E-If, E-Succ, E-Pred and E-IsZero only carry a step down to its redex. `Wrap`
+/// reduces a numeral to its residue under `EvalOptions::modulus`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StepRule {
+    IfTrue,
+    IfFalse,
+    PredZero,
+    PredSucc,
+    IsZeroZero,
+    IsZeroSucc,
+    Wrap,
+}
+
+impl StepRule {
+    pub fn name(self) -> &'static str {
+        match self {
+            StepRule::IfTrue => "E-IfTrue",
+            StepRule::IfFalse => "E-IfFalse",
+            StepRule::PredZero => "E-PredZero",
+            StepRule::PredSucc => "E-PredSucc",
+            StepRule::IsZeroZero => "E-IsZeroZero",
+            StepRule::IsZeroSucc => "E-IsZeroSucc",
+            StepRule::Wrap => "E-Wrap",
+        }
+    }
+}
+
+impl std::fmt::Display for StepRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// One step `t → term` of the small-step relation: `rule` rewrote the subterm at child
+/// path `redex` of `t`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmallStep {
+    pub rule: StepRule,
+    pub redex: Vec<usize>,
+    pub term: AST,
+}
+
+/// A derivation of `term ⇓ value`, concluded by `rule` from `premises`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Derivation {
+    pub rule: EvalRule,
+    pub term: AST,
+    pub value: AST,
+    pub premises: Vec<Derivation>,
+}
+
+impl Derivation {
+    /// The rule applications in the order `eval_ast` completes them, premises first.
+    pub fn steps(&self) -> Vec<&Derivation> {
+        let mut steps = Vec::new();
+        self.collect_steps(&mut steps);
+        steps
+    }
+
+    fn collect_steps<'d>(&'d self, steps: &mut Vec<&'d Derivation>) {
+        for premise in &self.premises {
+            premise.collect_steps(steps);
+        }
+        steps.push(self);
+    }
+}
+
+fn not_numeric(operator: &'static str, found: &AST) -> ArithError {
+    ArithError::NotNumericError {
+        operator,
+        found: found.clone(),
+    }
+}
+
+/// Evaluates `v` like `eval_ast`, recording which rule justified each step.
+pub fn derive(v: AST) -> Result<Derivation, ArithError> {
+    derive_with(v, &EvalOptions::default())
+}
+
+/// Like `derive`, but following the semantics selected by `options`.
+pub fn derive_with(v: AST, options: &EvalOptions) -> Result<Derivation, ArithError> {
+    let term = v.clone();
+    let (rule, value, premises) = match v {
+        v if is_val(&v) => (EvalRule::Value, options.wrap(v), vec![]),
+        AST::IfThenElse(cond, then, els) => {
+            let cond = derive_with(*cond, options)?;
+            let (rule, branch) = match cond.value {
+                AST::True => (EvalRule::IfTrue, *then),
+                AST::False => (EvalRule::IfFalse, *els),
+                ref v => return Err(ArithError::UnknownRuleError(v.clone())),
+            };
+            let branch = derive_with(branch, options)?;
+            (rule, branch.value.clone(), vec![cond, branch])
+        }
+        AST::Succ(v) => {
+            let arg = derive_with(*v, options)?;
+            match &arg.value {
+                v if is_numeric_val(v) => (
+                    EvalRule::Succ,
+                    options.wrap(AST::Succ(Box::new(v.clone()))),
+                    vec![arg],
+                ),
+                v => return Err(not_numeric("succ", v)),
+            }
+        }
+        AST::Pred(v) => {
+            let arg = derive_with(*v, options)?;
+            match &arg.value {
+                AST::Zero if options.strict_numerals => {
+                    return Err(ArithError::StuckTerm(AST::Pred(Box::new(AST::Zero))))
+                }
+                AST::Zero => (EvalRule::PredZero, AST::Zero, vec![arg]),
+                AST::Succ(v) if is_numeric_val(v) => (EvalRule::PredSucc, (**v).clone(), vec![arg]),
+                v => return Err(not_numeric("pred", v)),
+            }
+        }
+        AST::IsZero(v) => {
+            let arg = derive_with(*v, options)?;
+            match &arg.value {
+                AST::Zero => (EvalRule::IsZeroZero, AST::True, vec![arg]),
+                AST::Succ(v) if is_numeric_val(v) => (EvalRule::IsZeroSucc, AST::False, vec![arg]),
+                v => return Err(not_numeric("iszero", v)),
+            }
+        }
+        v => return Err(ArithError::UnknownRuleError(v)),
+    };
+    Ok(Derivation {
+        rule,
+        term,
+        value,
+        premises,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eval_ast, eval_small_traced, try_parse};
+
+    fn rules(input: &str) -> Vec<EvalRule> {
+        let derivation = derive(try_parse(input).unwrap()).unwrap();
+        derivation.steps().iter().map(|step| step.rule).collect()
+    }
+
+    #[test]
+    fn test_explain_pred_succ() {
+        let derivation = derive(try_parse("pred succ 0").unwrap()).unwrap();
+        let steps = derivation.steps();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(steps[0].rule, EvalRule::Value);
+        assert_eq!(steps[1].rule.name(), "B-PredSucc");
+        assert_eq!(steps[1].value, AST::Zero);
+    }
+
+    #[test]
+    fn test_explain_small_step() {
+        let (steps, value) =
+            eval_small_traced(try_parse("pred succ 0").unwrap(), &EvalOptions::default());
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].rule.name(), "E-PredSucc");
+        assert_eq!(steps[0].term, AST::Zero);
+        assert_eq!(value.unwrap(), AST::Zero);
+
+        let input = try_parse("if iszero pred 1 then succ (if true then 0 else 1) else 0");
+        let (steps, value) = eval_small_traced(input.unwrap(), &EvalOptions::default());
+        let rules: Vec<_> = steps
+            .iter()
+            .map(|step| (step.rule, step.redex.len()))
+            .collect();
+        assert_eq!(
+            rules,
+            [
+                (StepRule::PredSucc, 2),
+                (StepRule::IsZeroZero, 1),
+                (StepRule::IfTrue, 0),
+                (StepRule::IfTrue, 1),
+            ]
+        );
+        assert_eq!(value.unwrap(), AST::Succ(Box::new(AST::Zero)));
+
+        let (steps, value) =
+            eval_small_traced(try_parse("succ iszero 0").unwrap(), &EvalOptions::default());
+        assert_eq!(steps.len(), 1);
+        assert!(matches!(value, Err(ArithError::NotNumericError { .. })));
+    }
+
+    #[test]
+    fn test_explain_rules() {
+        assert_eq!(
+            rules("if iszero 0 then succ pred 0 else 0"),
+            vec![
+                EvalRule::Value,
+                EvalRule::IsZeroZero,
+                EvalRule::Value,
+                EvalRule::PredZero,
+                EvalRule::Succ,
+                EvalRule::IfTrue,
+            ]
+        );
+        let input = try_parse("if iszero succ 0 then 0 else pred succ succ 0").unwrap();
+        let derivation = derive(input.clone()).unwrap();
+        assert_eq!(derivation.rule, EvalRule::IfFalse);
+        assert_eq!(derivation.value, eval_ast(input).unwrap());
+        assert!(derive(try_parse("iszero true").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_explain_strict_numerals() {
+        let strict = EvalOptions {
+            strict_numerals: true,
+            ..EvalOptions::default()
+        };
+        let input = try_parse("pred pred 0").unwrap();
+        assert_eq!(derive(input.clone()).unwrap().value, AST::Zero);
+        assert!(matches!(
+            derive_with(input, &strict),
+            Err(ArithError::StuckTerm(_))
+        ));
+    }
+}
@@ -0,0 +1,142 @@
+use crate::AST;
+
+pub use tapl_gen::{Gen, Rng};
+
+fn gen_literal(rng: &mut Rng) -> AST {
+    match rng.below(3) {
+        0 => AST::True,
+        1 => AST::False,
+        _ => AST::Zero,
+    }
+}
+
+fn boxed(v: AST) -> Box<AST> {
+    Box::new(v)
+}
+
+/// Generates a random term with `arith_size` at most `g.size()`.
+pub fn sized_term(g: &mut Gen) -> AST {
+    bounded_term(g, u128::MAX)
+}
+
+/// Like `sized_term`, but also with `arith_depth` at most `max_depth`.
+pub fn bounded_term(g: &mut Gen, max_depth: u128) -> AST {
+    if max_depth <= 1 {
+        return gen_literal(&mut g.rng);
+    }
+    let below = |g: &mut Gen| bounded_term(g, max_depth - 1);
+    let leaf = |g: &mut Gen| gen_literal(&mut g.rng);
+    let unary = |g: &mut Gen| {
+        let size = g.size() - 1;
+        let v = boxed(g.resize(size, below));
+        match g.rng.below(3) {
+            0 => AST::Succ(v),
+            1 => AST::Pred(v),
+            _ => AST::IsZero(v),
+        }
+    };
+    let if_then_else = |g: &mut Gen| {
+        if g.size() < 4 {
+            return unary(g);
+        }
+        let mut v = g.children(3, below).into_iter().map(boxed);
+        AST::IfThenElse(v.next().unwrap(), v.next().unwrap(), v.next().unwrap())
+    };
+    g.sized(&[(1, &leaf)], &[(3, &unary), (1, &if_then_else)])
+}
+
+fn terms_of_size(size: u128) -> Box<dyn Iterator<Item = AST>> {
+    match size {
+        0 => Box::new(std::iter::empty()),
+        1 => Box::new([AST::True, AST::False, AST::Zero].into_iter()),
+        _ => {
+            let unary = [AST::Succ, AST::Pred, AST::IsZero]
+                .into_iter()
+                .flat_map(move |op| terms_of_size(size - 1).map(move |v| op(Box::new(v))));
+            // The if node itself accounts for one unit of size.
+            let splits = (1..size - 1)
+                .flat_map(move |a| (1..size - 1 - a).map(move |b| (a, b, size - 1 - a - b)));
+            let ifs = splits.flat_map(|(a, b, c)| {
+                terms_of_size(a).flat_map(move |cond| {
+                    terms_of_size(b).flat_map(move |then| {
+                        let cond = cond.clone();
+                        terms_of_size(c).map(move |els| {
+                            AST::IfThenElse(
+                                Box::new(cond.clone()),
+                                Box::new(then.clone()),
+                                Box::new(els),
+                            )
+                        })
+                    })
+                })
+            });
+            Box::new(unary.chain(ifs))
+        }
+    }
+}
+
+/// Lazily yields every term with `arith_size` at most `max_size`, smallest first.
+pub fn enumerate_arith(max_size: u128) -> impl Iterator<Item = AST> {
+    (1..=max_size).flat_map(terms_of_size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{arith_size, eval_ast};
+
+    #[test]
+    fn test_sized_term_deterministic() {
+        let term = |seed| sized_term(&mut Gen::new(seed, 8));
+        assert_eq!(term(42), term(42));
+        assert_ne!(term(1), term(2));
+        let leaf = sized_term(&mut Gen::new(3, 1));
+        assert!(matches!(leaf, AST::True | AST::False | AST::Zero));
+    }
+
+    #[test]
+    fn test_bounded_term_depth() {
+        let mut g = Gen::new(5, 40);
+        for _ in 0..200 {
+            assert!(crate::arith_depth(&bounded_term(&mut g, 4)) <= 4);
+        }
+        let leaf = bounded_term(&mut g, 1);
+        assert!(matches!(leaf, AST::True | AST::False | AST::Zero));
+    }
+
+    #[test]
+    fn test_sized_term_coverage() {
+        let mut g = Gen::new(17, 12);
+        let terms: Vec<AST> = (0..1000).map(|_| sized_term(&mut g)).collect();
+        assert!(terms.iter().all(|v| arith_size(v) <= 12));
+        let seen = |f: fn(&AST) -> bool| terms.iter().filter(|v| f(v)).count();
+        assert!(seen(|v| matches!(v, AST::True | AST::False | AST::Zero)) > 50);
+        assert!(seen(|v| matches!(v, AST::Succ(_))) > 50);
+        assert!(seen(|v| matches!(v, AST::Pred(_))) > 50);
+        assert!(seen(|v| matches!(v, AST::IsZero(_))) > 50);
+        assert!(seen(|v| matches!(v, AST::IfThenElse(..))) > 50);
+        assert!(terms.iter().any(|v| arith_size(v) >= 10));
+    }
+
+    #[test]
+    fn test_enumerate_counts() {
+        let count = |size| {
+            enumerate_arith(size)
+                .filter(|v| arith_size(v) == size)
+                .count()
+        };
+        assert_eq!(count(1), 3);
+        assert_eq!(count(2), 9);
+        assert_eq!(count(4), 81 + 27);
+        assert_eq!(enumerate_arith(2).count(), 12);
+        assert!(enumerate_arith(5).all(|v| arith_size(&v) <= 5));
+    }
+
+    #[test]
+    fn test_enumerate_is_lazy() {
+        let first: Vec<_> = enumerate_arith(u128::MAX).take(4).collect();
+        assert_eq!(first[3], AST::Succ(Box::new(AST::True)));
+        // every small term either evaluates to a value or gets stuck, without panicking
+        assert!(enumerate_arith(5).all(|v| eval_ast(v).map_or(true, |v| crate::is_val(&v))));
+    }
+}
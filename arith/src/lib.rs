@@ -0,0 +1,1362 @@
+use pest::{
+    iterators::{Pair, Pairs},
+    Parser,
+};
+use pest_derive::Parser;
+use tapl_diagnostics::{Diagnostic, Span};
+use tapl_syntax::TermLike;
+
+pub mod batch;
+pub mod cli;
+pub mod explain;
+pub mod gen;
+mod macros;
+pub mod output;
+pub mod pretty;
+pub mod spanned;
+pub mod surface;
+pub mod trace;
+
+use explain::{EvalRule, SmallStep, StepRule};
+use spanned::{Node, Spanned};
+use surface::{desugar, SpanTree, SurfaceAST};
+
+#[derive(Parser)]
+#[grammar = "arith.pest"]
+struct ArithParser;
+
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, PartialEq)]
+pub enum AST {
+    True,
+    False,
+    Zero,
+    Succ(Box<AST>),
+    Pred(Box<AST>),
+    IsZero(Box<AST>),
+    IfThenElse(Box<AST>, Box<AST>, Box<AST>),
+}
+
+trait TryTake<T, E> {
+    fn try_take(&mut self) -> Result<T, E>;
+}
+
+impl<'i> TryTake<Pair<'i, Rule>, ArithError> for Pairs<'i, Rule> {
+    fn try_take(&mut self) -> Result<Pair<'i, Rule>, ArithError> {
+        self.next().ok_or(ArithError::EmptyPairsError)
+    }
+}
+
+// Numerals desugar to a chain of `succ`, so keep them small enough to evaluate.
+pub const MAX_NUMERAL: u64 = 1 << 12;
+
+impl TermLike for AST {
+    fn children(&self) -> Vec<&Self> {
+        match self {
+            AST::True | AST::False | AST::Zero => vec![],
+            AST::Succ(v) | AST::Pred(v) | AST::IsZero(v) => vec![v],
+            AST::IfThenElse(cond, then, els) => vec![cond, then, els],
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Self> {
+        match self {
+            AST::True | AST::False | AST::Zero => vec![],
+            AST::Succ(v) | AST::Pred(v) | AST::IsZero(v) => vec![v],
+            AST::IfThenElse(cond, then, els) => vec![cond, then, els],
+        }
+    }
+
+    fn label(&self) -> String {
+        let label = match self {
+            AST::True => "true",
+            AST::False => "false",
+            AST::Zero => "0",
+            AST::Succ(_) => "succ",
+            AST::Pred(_) => "pred",
+            AST::IsZero(_) => "iszero",
+            AST::IfThenElse(..) => "if",
+        };
+        label.to_owned()
+    }
+
+    fn from_parts(label: &str, children: Vec<Self>) -> Option<Self> {
+        let arity = children.len();
+        let mut children = children.into_iter().map(Box::new);
+        let mut next = || children.next().unwrap();
+        Some(match (label, arity) {
+            ("true", 0) => AST::True,
+            ("false", 0) => AST::False,
+            ("0", 0) => AST::Zero,
+            ("succ", 1) => AST::Succ(next()),
+            ("pred", 1) => AST::Pred(next()),
+            ("iszero", 1) => AST::IsZero(next()),
+            ("if", 3) => AST::IfThenElse(next(), next(), next()),
+            _ => return None,
+        })
+    }
+}
+
+impl std::fmt::Display for AST {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", pretty::pretty(self, usize::MAX))
+    }
+}
+
+pub fn is_numeric_val(v: &AST) -> bool {
+    match v {
+        AST::Zero => true,
+        AST::Succ(v) => is_numeric_val(v),
+        _ => false,
+    }
+}
+
+pub fn is_val(v: &AST) -> bool {
+    match v {
+        AST::True | AST::False => true,
+        v if is_numeric_val(v) => true,
+        _ => false,
+    }
+}
+
+/// Knobs for course variants whose semantics differ from the book's.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EvalOptions {
+    /// Treat `pred 0` as stuck instead of B-PredZero's `0`.
+    pub strict_numerals: bool,
+    /// Wrap `succ` around at this bound, like a machine integer. Modulo 0 nothing wraps,
+    /// as in ℤ/0ℤ.
+    pub modulus: Option<u128>,
+}
+
+impl EvalOptions {
+    /// Whether `wrap` would change `v`.
+    pub(crate) fn wraps(&self, v: &AST) -> bool {
+        let modulus = self.modulus.filter(|&m| m > 0);
+        modulus.zip(numeral_value(v)).is_some_and(|(m, n)| n >= m)
+    }
+
+    /// Reduces a numeric value modulo `modulus`, so `succ` of the largest value is `0`.
+    pub(crate) fn wrap(&self, v: AST) -> AST {
+        match self.modulus.filter(|&m| m > 0).zip(numeral_value(&v)) {
+            Some((m, n)) if n >= m => (0..n % m).fold(AST::Zero, |v, _| AST::Succ(Box::new(v))),
+            _ => v,
+        }
+    }
+}
+
+pub fn eval_ast(v: AST) -> Result<AST, ArithError> {
+    eval_ast_with(v, &EvalOptions::default())
+}
+
+pub fn eval_ast_with(v: AST, options: &EvalOptions) -> Result<AST, ArithError> {
+    match v {
+        v if is_val(&v) => Ok(options.wrap(v)), // B-Value
+        AST::IfThenElse(cond, then, els) => {
+            let cond = eval_ast_with(*cond, options)?;
+            match cond {
+                AST::True => eval_ast_with(*then, options), // B-IfTrue
+                AST::False => eval_ast_with(*els, options), // B-IfFalse
+                v => Err(ArithError::UnknownRuleError(v)),
+            }
+        }
+        AST::Succ(v) => {
+            let v = eval_ast_with(*v, options)?;
+            match v {
+                v if is_numeric_val(&v) => Ok(options.wrap(AST::Succ(Box::new(v)))), // B-Succ
+                v => Err(ArithError::NotNumericError {
+                    operator: "succ",
+                    found: v,
+                }),
+            }
+        }
+        AST::Pred(v) => {
+            let v = eval_ast_with(*v, options)?;
+            match v {
+                AST::Zero if options.strict_numerals => {
+                    Err(ArithError::StuckTerm(AST::Pred(Box::new(AST::Zero))))
+                }
+                AST::Zero => Ok(AST::Zero), // B-PredZero
+                AST::Succ(v) if is_numeric_val(&v) => Ok(*v), // B-PredSucc
+                v => Err(ArithError::NotNumericError {
+                    operator: "pred",
+                    found: v,
+                }),
+            }
+        }
+        AST::IsZero(v) => {
+            let v = eval_ast_with(*v, options)?;
+            match v {
+                AST::Zero => Ok(AST::True),                           // B-IsZeroZero
+                AST::Succ(v) if is_numeric_val(&v) => Ok(AST::False), // B-IsZeroSucc
+                v => Err(ArithError::NotNumericError {
+                    operator: "iszero",
+                    found: v,
+                }),
+            }
+        }
+        v => Err(ArithError::UnknownRuleError(v)),
+    }
+}
+
+/// One step of the small-step relation `t → t'`, by the E-rules of TAPL figures 3-1 and
+/// 3-2. Normal forms have no step: a value fails with `UnknownRuleError`, and a stuck
+/// term with the error `eval_ast` reports for it.
+pub fn eval1(v: AST) -> Result<AST, ArithError> {
+    eval1_with(v, &EvalOptions::default())
+}
+
+/// Like `eval1`, under the same `options` as `eval_ast_with`: `pred 0` is stuck with strict
+/// numerals, and a numeral past the modulus steps by E-Wrap to its residue before anything
+/// looks at it, so both semantics agree on every term.
+pub fn eval1_with(v: AST, options: &EvalOptions) -> Result<AST, ArithError> {
+    eval1_explained_with(v, options).map(|step| step.term)
+}
+
+// A congruence rule: steps `v` where `plug` puts it back.
+fn step_inside(
+    v: AST,
+    options: &EvalOptions,
+    plug: impl FnOnce(Box<AST>) -> AST,
+) -> Result<SmallStep, ArithError> {
+    let mut step = eval1_explained_with(v, options)?;
+    step.redex.push(0);
+    step.term = plug(Box::new(step.term));
+    Ok(step)
+}
+
+/// Like `eval1`, also naming the axiom that fired and where. The congruence rules only
+/// ever step a first child, so the redex path is all zeros.
+pub fn eval1_explained(v: AST) -> Result<SmallStep, ArithError> {
+    eval1_explained_with(v, &EvalOptions::default())
+}
+
+/// `eval1_explained` under `options`, as for `eval1_with`.
+pub fn eval1_explained_with(v: AST, options: &EvalOptions) -> Result<SmallStep, ArithError> {
+    let not_numeric = |operator, found| ArithError::NotNumericError { operator, found };
+    let axiom = |rule, term| {
+        Ok(SmallStep {
+            rule,
+            redex: vec![],
+            term,
+        })
+    };
+    let wraps = |v: &AST| options.wraps(v);
+    match v {
+        v if wraps(&v) => axiom(StepRule::Wrap, options.wrap(v)),
+        v if is_val(&v) => Err(ArithError::UnknownRuleError(v)),
+        AST::IfThenElse(cond, then, els) => match *cond {
+            AST::True => axiom(StepRule::IfTrue, *then),
+            AST::False => axiom(StepRule::IfFalse, *els),
+            cond if is_val(&cond) && !wraps(&cond) => Err(ArithError::UnknownRuleError(cond)),
+            // E-If
+            cond => step_inside(cond, options, |cond| AST::IfThenElse(cond, then, els)),
+        },
+        // `succ nv` is itself a value, so here the operand is a boolean or not yet a value.
+        AST::Succ(v) => match *v {
+            v if is_val(&v) => Err(not_numeric("succ", v)),
+            v => step_inside(v, options, AST::Succ), // E-Succ
+        },
+        AST::Pred(v) => match *v {
+            v if wraps(&v) => step_inside(v, options, AST::Pred), // E-Pred
+            AST::Zero if options.strict_numerals => {
+                Err(ArithError::StuckTerm(AST::Pred(Box::new(AST::Zero))))
+            }
+            AST::Zero => axiom(StepRule::PredZero, AST::Zero),
+            AST::Succ(nv) if is_numeric_val(&nv) => axiom(StepRule::PredSucc, *nv),
+            v if is_val(&v) => Err(not_numeric("pred", v)),
+            v => step_inside(v, options, AST::Pred), // E-Pred
+        },
+        AST::IsZero(v) => match *v {
+            v if wraps(&v) => step_inside(v, options, AST::IsZero), // E-IsZero
+            AST::Zero => axiom(StepRule::IsZeroZero, AST::True),
+            AST::Succ(nv) if is_numeric_val(&nv) => axiom(StepRule::IsZeroSucc, AST::False),
+            v if is_val(&v) => Err(not_numeric("iszero", v)),
+            v => step_inside(v, options, AST::IsZero), // E-IsZero
+        },
+        v => Err(ArithError::UnknownRuleError(v)),
+    }
+}
+
+/// Steps `v` with `eval1` until it is a value. Arith's two semantics agree, so this
+/// returns what `eval_ast` does, errors included.
+pub fn eval_small(v: AST) -> Result<AST, ArithError> {
+    eval_small_with(v, &EvalOptions::default())
+}
+
+/// Steps `v` with `eval1_with` until it is a value that does not wrap, returning what
+/// `eval_ast_with` does under the same `options`.
+pub fn eval_small_with(mut v: AST, options: &EvalOptions) -> Result<AST, ArithError> {
+    while !is_val(&v) || options.wraps(&v) {
+        v = eval1_with(v, options)?;
+    }
+    Ok(v)
+}
+
+/// Like `eval_small_with`, also returning every step taken, up to the point where
+/// evaluation got stuck.
+pub fn eval_small_traced(
+    mut v: AST,
+    options: &EvalOptions,
+) -> (Vec<SmallStep>, Result<AST, ArithError>) {
+    let mut steps = Vec::new();
+    while !is_val(&v) || options.wraps(&v) {
+        match eval1_explained_with(v, options) {
+            Ok(step) => {
+                v = step.term.clone();
+                steps.push(step);
+            }
+            Err(e) => return (steps, Err(e)),
+        }
+    }
+    (steps, Ok(v))
+}
+
+/// The terms `v` reduces to, one `eval1` step at a time, each computed only once it is
+/// asked for. The sequence ends at a normal form, which `eval1` tells apart as a value or
+/// a stuck term.
+pub struct ReductionIter {
+    current: Option<AST>,
+}
+
+impl Iterator for ReductionIter {
+    type Item = AST;
+
+    fn next(&mut self) -> Option<AST> {
+        let next = eval1(self.current.take()?).ok()?;
+        self.current = Some(next.clone());
+        Some(next)
+    }
+}
+
+pub fn reductions(v: AST) -> ReductionIter {
+    ReductionIter { current: Some(v) }
+}
+
+/// Reduces `v` to a value first, then applies B-IsZeroZero/B-IsZeroSucc, so `v` need
+/// not already be a canonical numeral.
+pub fn iszero_eval(v: AST) -> Result<AST, ArithError> {
+    eval_ast(AST::IsZero(Box::new(v)))
+}
+
+pub fn numeral_value(v: &AST) -> Option<u128> {
+    let mut n = 0;
+    let mut v = v;
+    loop {
+        match v {
+            AST::Zero => return Some(n),
+            AST::Succ(pred) => {
+                n += 1;
+                v = pred;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Evaluates both terms and compares them by value, or returns `None` if
+/// either evaluates to a boolean.
+pub fn cmp_numeric(a: &AST, b: &AST) -> Result<Option<std::cmp::Ordering>, ArithError> {
+    let a = eval_ast(a.clone())?;
+    let b = eval_ast(b.clone())?;
+    Ok(numeral_value(&a)
+        .zip(numeral_value(&b))
+        .map(|(a, b)| a.cmp(&b)))
+}
+
+// The surrounding term of the subterm currently being evaluated, innermost last.
+enum Frame {
+    Succ,
+    Pred,
+    IsZero,
+    IfCond(AST, AST),
+}
+
+fn plug(frames: &[Frame], v: AST) -> AST {
+    frames.iter().rev().fold(v, |v, frame| match frame {
+        Frame::Succ => AST::Succ(Box::new(v)),
+        Frame::Pred => AST::Pred(Box::new(v)),
+        Frame::IsZero => AST::IsZero(Box::new(v)),
+        Frame::IfCond(then, els) => {
+            AST::IfThenElse(Box::new(v), Box::new(then.clone()), Box::new(els.clone()))
+        }
+    })
+}
+
+/// One rewrite of an evaluation trace: `rule` replaced the subterm at child path
+/// `redex` of the previous whole-program term, giving `term`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceStep {
+    pub rule: EvalRule,
+    pub redex: Vec<usize>,
+    pub term: AST,
+}
+
+struct Trace {
+    initial: AST,
+    steps: Vec<TraceStep>,
+}
+
+impl Trace {
+    fn record(&mut self, frames: &[Frame], v: &AST, rule: EvalRule) {
+        let term = plug(frames, v.clone());
+        let last = self.steps.last().map_or(&self.initial, |step| &step.term);
+        if *last != term {
+            // Every frame holds the subterm being evaluated as its first child.
+            let redex = vec![0; frames.len()];
+            self.steps.push(TraceStep { rule, redex, term });
+        }
+    }
+}
+
+fn eval_traced(
+    v: AST,
+    options: &EvalOptions,
+    frames: &mut Vec<Frame>,
+    trace: &mut Trace,
+) -> Result<AST, ArithError> {
+    let mut eval_in = |frame, v| {
+        frames.push(frame);
+        let v = eval_traced(v, options, frames, trace);
+        frames.pop();
+        v
+    };
+    let (rule, result) = match v {
+        v if is_val(&v) => (EvalRule::Value, options.wrap(v)),
+        AST::IfThenElse(cond, then, els) => {
+            let frame = Frame::IfCond((*then).clone(), (*els).clone());
+            let (rule, branch) = match eval_in(frame, *cond)? {
+                AST::True => (EvalRule::IfTrue, *then),
+                AST::False => (EvalRule::IfFalse, *els),
+                v => return Err(ArithError::UnknownRuleError(v)),
+            };
+            trace.record(frames, &branch, rule);
+            (rule, eval_traced(branch, options, frames, trace)?)
+        }
+        AST::Succ(v) => match eval_in(Frame::Succ, *v)? {
+            v if is_numeric_val(&v) => (EvalRule::Succ, options.wrap(AST::Succ(Box::new(v)))),
+            v => {
+                return Err(ArithError::NotNumericError {
+                    operator: "succ",
+                    found: v,
+                })
+            }
+        },
+        AST::Pred(v) => match eval_in(Frame::Pred, *v)? {
+            AST::Zero if options.strict_numerals => {
+                return Err(ArithError::StuckTerm(AST::Pred(Box::new(AST::Zero))))
+            }
+            AST::Zero => (EvalRule::PredZero, AST::Zero),
+            AST::Succ(v) if is_numeric_val(&v) => (EvalRule::PredSucc, *v),
+            v => {
+                return Err(ArithError::NotNumericError {
+                    operator: "pred",
+                    found: v,
+                })
+            }
+        },
+        AST::IsZero(v) => match eval_in(Frame::IsZero, *v)? {
+            AST::Zero => (EvalRule::IsZeroZero, AST::True),
+            AST::Succ(v) if is_numeric_val(&v) => (EvalRule::IsZeroSucc, AST::False),
+            v => {
+                return Err(ArithError::NotNumericError {
+                    operator: "iszero",
+                    found: v,
+                })
+            }
+        },
+        v => return Err(ArithError::UnknownRuleError(v)),
+    };
+    trace.record(frames, &result, rule);
+    Ok(result)
+}
+
+/// Evaluates `v` like `eval_ast_with`, also returning every rewrite of the whole
+/// program made along the way, up to the point where evaluation got stuck.
+pub fn eval_ast_traced(v: AST, options: &EvalOptions) -> (Vec<TraceStep>, Result<AST, ArithError>) {
+    let mut trace = Trace {
+        initial: v.clone(),
+        steps: Vec::new(),
+    };
+    let result = eval_traced(v, options, &mut Vec::new(), &mut trace);
+    (trace.steps, result)
+}
+
+/// Like `eval_ast`, but on failure also returns every whole-program term reached
+/// before evaluation got stuck, starting with `v` itself.
+pub fn eval_ast_verbose(v: AST) -> Result<AST, (ArithError, Vec<AST>)> {
+    let initial = v.clone();
+    let (steps, result) = eval_ast_traced(v, &EvalOptions::default());
+    result.map_err(|e| {
+        let terms = std::iter::once(initial).chain(steps.into_iter().map(|step| step.term));
+        (e, terms.collect())
+    })
+}
+
+/// The deepest term accepted anywhere, whether built by hand or parsed, and the deepest
+/// that source text may nest. Recursing this deep needs more stack than a debug build's main
+/// thread has, so deep parses and evaluations run on threads of their own.
+pub const MAX_DEPTH: u128 = 1 << 13;
+
+/// An upper bound on how deeply `input` nests, computed without recursing.
+///
+/// Parentheses, `if`s and infix operators each open a level that is only assumed closed at
+/// the enclosing `)`; a run of prefix operators closes at the end of its operand. This bounds
+/// both how deeply the parser recurses and how deep the folded `SurfaceAST` gets. The scan
+/// matches keywords the way the grammar does, without requiring word boundaries, so it stays
+/// an over-approximation even for input that fails to parse.
+fn nesting(input: &str) -> usize {
+    const KEYWORDS: [&str; 11] = [
+        "if", "then", "else", "succ", "pred", "iszero", "not", "and", "or", "true", "false",
+    ];
+    // Per parenthesised level: levels that stay open, and the pending prefix operators.
+    let mut levels = vec![(0, 0)];
+    let mut depth: usize = 0;
+    let mut max = 0;
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        let keyword = KEYWORDS.iter().find(|k| rest.starts_with(**k));
+        let len = keyword.map_or(c.len_utf8(), |k| k.len());
+        let nested = levels.len() > 1;
+        let (open, chain) = levels.last_mut().unwrap();
+        match keyword.copied() {
+            Some("if") => {
+                *open += *chain + 1;
+                *chain = 0;
+                depth += 1;
+            }
+            Some("succ" | "pred" | "iszero" | "not") => {
+                *chain += 1;
+                depth += 1;
+            }
+            Some("and" | "or") => {
+                depth -= *chain;
+                *open += 1;
+                *chain = 0;
+                depth += 1;
+            }
+            Some(_) => {
+                depth -= *chain;
+                *chain = 0;
+            }
+            None if c == '(' => {
+                levels.push((0, 0));
+                depth += 1;
+            }
+            None if c == ')' && nested => {
+                let (open, chain) = levels.pop().unwrap();
+                depth -= open + chain + 1;
+                let (_, chain) = levels.last_mut().unwrap();
+                depth -= *chain;
+                *chain = 0;
+            }
+            None if c.is_ascii_digit() => {
+                depth -= *chain;
+                *chain = 0;
+            }
+            None => {}
+        }
+        max = max.max(depth);
+        rest = &rest[len..];
+    }
+    max
+}
+
+/// Checks that a term is safe to hand to the evaluator.
+///
+/// Every `AST` value is structurally well-formed by construction: each node owns exactly
+/// the children its rule requires, so there is no malformed shape to reject. The one thing
+/// that can go wrong with hand-built terms is nesting depth, which the parser bounds but
+/// direct construction does not; terms deeper than `MAX_DEPTH` are rejected rather than
+/// risking a stack overflow. The walk itself is iterative for the same reason.
+pub fn validate(v: &AST) -> Result<(), ArithError> {
+    let mut stack = vec![(v, 1)];
+    while let Some((v, depth)) = stack.pop() {
+        if depth > MAX_DEPTH {
+            return Err(ArithError::TooDeepError);
+        }
+        match v {
+            AST::True | AST::False | AST::Zero => {}
+            AST::Succ(v) | AST::Pred(v) | AST::IsZero(v) => stack.push((v, depth + 1)),
+            AST::IfThenElse(cond, then, els) => {
+                stack.extend([(&**cond, depth + 1), (then, depth + 1), (els, depth + 1)])
+            }
+        }
+    }
+    Ok(())
+}
+
+pub fn arith_size(v: &AST) -> u128 {
+    tapl_syntax::size(v) as u128
+}
+
+pub fn arith_depth(v: &AST) -> u128 {
+    tapl_syntax::depth(v) as u128
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug)]
+pub enum ArithError {
+    ParseError(Box<pest::error::Error<Rule>>),
+    UnexpectedNodeError(Rule),
+    UnknownRuleError(AST),
+    NotNumericError { operator: &'static str, found: AST },
+    StuckTerm(AST),
+    NumeralTooLargeError(String),
+    TooDeepError,
+    InternalError(String),
+    EmptyPairsError,
+    IoError(std::io::Error),
+}
+
+impl std::fmt::Display for ArithError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArithError::ParseError(_) => write!(f, "failed to parse input"),
+            ArithError::UnexpectedNodeError(rule) => {
+                write!(f, "unexpected node {:?} in parse tree", rule)
+            }
+            ArithError::UnknownRuleError(v) => write!(f, "no evaluation rule applies to {}", v),
+            ArithError::NotNumericError { operator, found } => {
+                write!(
+                    f,
+                    "`{}` expects a numeric value but got {}",
+                    operator, found
+                )
+            }
+            ArithError::StuckTerm(v) => write!(f, "{} is stuck under strict numerals", v),
+            ArithError::NumeralTooLargeError(n) => {
+                write!(f, "numeral {} exceeds the maximum of {}", n, MAX_NUMERAL)
+            }
+            ArithError::TooDeepError => {
+                write!(f, "term nesting depth exceeds the maximum of {}", MAX_DEPTH)
+            }
+            ArithError::InternalError(message) => write!(f, "internal error: {}", message),
+            ArithError::EmptyPairsError => write!(f, "parse tree ended unexpectedly"),
+            ArithError::IoError(_) => write!(f, "failed to read input"),
+        }
+    }
+}
+
+/// Written out rather than derived, so that the source of a parse error is the pest error
+/// itself and not the box it is kept in.
+impl std::error::Error for ArithError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ArithError::ParseError(e) => Some(&**e),
+            ArithError::IoError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Box<pest::error::Error<Rule>>> for ArithError {
+    fn from(e: Box<pest::error::Error<Rule>>) -> Self {
+        ArithError::ParseError(e)
+    }
+}
+
+impl From<std::io::Error> for ArithError {
+    fn from(e: std::io::Error) -> Self {
+        ArithError::IoError(e)
+    }
+}
+
+impl ArithError {
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        match self {
+            ArithError::ParseError(e) => Diagnostic::from_pest(self.to_string(), e),
+            ArithError::IoError(e) => Diagnostic::error(format!("{}: {}", self, e)),
+            _ => Diagnostic::error(self.to_string()),
+        }
+    }
+}
+
+// Per level of nesting, the parser and the conversions after it take up to this much stack
+// in a debug build, nested `if`s being the worst case. An optimised build needs an eighth.
+const PARSE_FRAME: usize = 32 << 10;
+
+// Input nested no deeper than this is parsed on the caller's stack.
+const SHALLOW: usize = 16;
+
+// Parses `input` and hands the tree to `build`, on a thread with room for both to recurse
+// if `input` nests deeply.
+fn parse_with<T: Send>(
+    input: &str,
+    build: impl FnOnce(Pair<'_, Rule>) -> Result<T, ArithError> + Send,
+) -> Result<T, ArithError> {
+    let nesting = nesting(input);
+    if nesting as u128 > MAX_DEPTH {
+        return Err(ArithError::TooDeepError);
+    }
+    let parse = || {
+        let pair = ArithParser::parse(Rule::Input, input)
+            .map_err(Box::new)?
+            .next()
+            .ok_or(ArithError::EmptyPairsError)?;
+        build(pair)
+    };
+    if nesting <= SHALLOW {
+        parse()
+    } else {
+        isolated((nesting + SHALLOW) * PARSE_FRAME, parse)
+    }
+}
+
+pub fn try_parse_surface(input: &str) -> Result<SurfaceAST, ArithError> {
+    parse_with(input, |input| SurfaceAST::try_from(input))
+}
+
+pub fn try_parse_surface_spanned(input: &str) -> Result<(SurfaceAST, SpanTree), ArithError> {
+    parse_with(input, |input| {
+        let spans = surface::span_tree(input.clone());
+        Ok((SurfaceAST::try_from(input)?, spans))
+    })
+}
+
+pub fn try_parse_spanned(input: &str) -> Result<Spanned<Node>, ArithError> {
+    parse_with(input, |input| {
+        let spans = surface::span_tree(input.clone());
+        let term = spanned::desugar_spanned(SurfaceAST::try_from(input)?, &spans);
+        match tapl_syntax::depth(&term) as u128 {
+            depth if depth > MAX_DEPTH => Err(ArithError::TooDeepError),
+            _ => Ok(term),
+        }
+    })
+}
+
+/// Parses and desugars `input`. Numerals can make the term deeper than the source nests, so
+/// the result is checked with `validate` too.
+pub fn try_parse(input: &str) -> Result<AST, ArithError> {
+    let v = parse_with(input, |input| Ok(desugar(SurfaceAST::try_from(input)?)))?;
+    if let Err(e) = validate(&v) {
+        dismantle(v);
+        return Err(e);
+    }
+    Ok(v)
+}
+
+/// Parses and evaluates `input` with `try_eval`, so any term it parses is safe to evaluate.
+pub fn eval(input: &str) -> Result<AST, ArithError> {
+    try_eval(try_parse(input)?, &EvalOptions::default())
+}
+
+// Room for a `MAX_DEPTH`-deep evaluation even in a debug build, where each level of
+// recursion takes a few kilobytes. Only the pages actually touched are committed.
+const ISOLATED_STACK: usize = 128 << 20;
+
+/// Runs `f` on a thread with enough stack to evaluate, type or print any term up to
+/// `MAX_DEPTH` deep. Command-line drivers run inside this.
+pub fn with_stack<T: Send>(f: impl FnOnce() -> T + Send) -> T {
+    std::thread::scope(|scope| {
+        std::thread::Builder::new()
+            .stack_size(ISOLATED_STACK)
+            .spawn_scoped(scope, f)
+            .expect("failed to spawn a thread")
+            .join()
+            .unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+    })
+}
+
+// Runs `f` on its own thread with a `stack`-byte stack, turning a panic into an error rather
+// than unwinding into the caller.
+fn isolated<T: Send>(
+    stack: usize,
+    f: impl FnOnce() -> Result<T, ArithError> + Send,
+) -> Result<T, ArithError> {
+    std::thread::scope(|scope| {
+        let worker = std::thread::Builder::new()
+            .stack_size(stack)
+            .spawn_scoped(scope, f)
+            .map_err(ArithError::IoError)?;
+        worker.join().unwrap_or_else(|payload| {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "evaluation panicked".to_owned());
+            Err(ArithError::InternalError(message))
+        })
+    })
+}
+
+// Takes a term apart one node at a time, where dropping it would recurse once per level.
+fn dismantle(v: AST) {
+    let mut stack = vec![v];
+    while let Some(v) = stack.pop() {
+        match v {
+            AST::True | AST::False | AST::Zero => {}
+            AST::Succ(v) | AST::Pred(v) | AST::IsZero(v) => stack.push(*v),
+            AST::IfThenElse(cond, then, els) => stack.extend([*cond, *then, *els]),
+        }
+    }
+}
+
+/// Like `eval_ast_with`, but total: terms that fail `validate` are rejected (and freed)
+/// without recursing, and anything else that goes wrong comes back as an error.
+pub fn try_eval(v: AST, options: &EvalOptions) -> Result<AST, ArithError> {
+    if let Err(e) = validate(&v) {
+        dismantle(v);
+        return Err(e);
+    }
+    let options = *options;
+    isolated(ISOLATED_STACK, move || eval_ast_with(v, &options))
+}
+
+/// Parses, evaluates and prints `src`, reporting every failure, panics included, as the
+/// error's message. Safe to call on any input.
+pub fn try_eval_str(src: &str) -> Result<String, String> {
+    isolated(ISOLATED_STACK, || {
+        let v = try_parse(src)?;
+        eval_ast(v).map(|v| v.to_string())
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Evaluates a program of `;`-separated terms one term at a time, only parsing
+/// each term once the previous result has been consumed. Statements are split as by
+/// `statements`, so error positions point into `src`.
+pub fn eval_stream(src: &str) -> impl Iterator<Item = Result<AST, ArithError>> + '_ {
+    statements(src).map(|(_, statement)| eval(&statement))
+}
+
+/// The non-blank `;`-separated statements of `src`, each with its trimmed span.
+///
+/// Everything before a statement is blanked out, keeping newlines and byte lengths, so
+/// positions in errors from parsing the returned text refer to `src`.
+pub fn statements(src: &str) -> impl Iterator<Item = (Span, String)> + '_ {
+    let mut start = 0;
+    src.split(';').filter_map(move |statement| {
+        let offset = start;
+        start += statement.len() + 1;
+        let trimmed = statement.trim_start();
+        if trimmed.is_empty() {
+            return None;
+        }
+        let begin = offset + statement.len() - trimmed.len();
+        let span = Span::new(begin, begin + trimmed.trim_end().len());
+        let masked = src[..offset]
+            .bytes()
+            .map(|b| if b == b'\n' { '\n' } else { ' ' })
+            .chain(statement.chars())
+            .collect();
+        Some((span, masked))
+    })
+}
+
+/// Parses each `;`-separated statement of `src` on its own, so one malformed
+/// statement does not hide the errors in the others. Returns the last statement
+/// that parsed, alongside every error in source order.
+pub fn try_parse_recovering(src: &str) -> (Option<AST>, Vec<ArithError>) {
+    let mut last = None;
+    let mut errors = Vec::new();
+    for (_, statement) in statements(src) {
+        match try_parse(&statement) {
+            Ok(v) => last = Some(v),
+            Err(e) => errors.push(e),
+        }
+    }
+    (last, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_parse() {
+        let input = "if iszero pred succ 0 then true else false";
+        let input = try_parse(input).unwrap();
+        assert_eq!(
+            input,
+            AST::IfThenElse(
+                Box::new(AST::IsZero(Box::new(AST::Pred(Box::new(AST::Succ(
+                    Box::new(AST::Zero)
+                )))))),
+                Box::new(AST::True),
+                Box::new(AST::False)
+            )
+        );
+    }
+
+    #[test]
+    fn test_numeric_ops() {
+        let input = "pred pred succ succ succ 0";
+        let input = try_parse(input).unwrap();
+        let output = eval_ast(input).unwrap();
+        assert_eq!(output, AST::Succ(Box::new(AST::Zero)));
+    }
+
+    #[test]
+    fn test_eval_if_else() {
+        let input = "if iszero succ 0 then true else false";
+        let input = try_parse(input).unwrap();
+        let output = eval_ast(input).unwrap();
+        assert_eq!(output, AST::False);
+    }
+
+    #[test]
+    fn test_arith_size_and_depth() {
+        let input = "if iszero succ 0 then if iszero pred 0 then true else succ 0 else false";
+        let input = try_parse(input).unwrap();
+        let size = arith_size(&input);
+        assert_eq!(size, 12);
+        let depth = arith_depth(&input);
+        assert_eq!(depth, 5);
+    }
+
+    #[test]
+    fn test_display() {
+        let input = "if iszero succ 0 then succ (if true then 0 else 0) else pred 0";
+        let input = try_parse(input).unwrap();
+        assert_eq!(
+            input.to_string(),
+            "if iszero succ 0 then succ (if true then 0 else 0) else pred 0"
+        );
+    }
+
+    #[test]
+    fn test_pretty_wraps() {
+        let input =
+            "if iszero pred succ 0 then if iszero 0 then succ succ 0 else pred 0 else false";
+        let input = try_parse(input).unwrap();
+        let output = pretty::pretty(&input, 30);
+        assert_eq!(
+            output,
+            "if iszero pred succ 0\n  then if iszero 0\n    then succ succ 0\n    else pred 0\n  else false"
+        );
+        assert!(output.lines().all(|line| line.len() <= 30));
+        assert_eq!(try_parse(&output).unwrap(), input);
+    }
+
+    #[test]
+    fn test_not_numeric_errors() {
+        let message = |input| eval(input).unwrap_err().to_string();
+        assert_eq!(
+            message("succ true"),
+            "`succ` expects a numeric value but got true"
+        );
+        assert_eq!(
+            message("pred false"),
+            "`pred` expects a numeric value but got false"
+        );
+        assert_eq!(
+            message("iszero false"),
+            "`iszero` expects a numeric value but got false"
+        );
+        assert!(matches!(
+            eval("succ iszero 0"),
+            Err(ArithError::NotNumericError {
+                operator: "succ",
+                found: AST::True
+            })
+        ));
+    }
+
+    #[test]
+    fn test_eval_verbose_prefix() {
+        let input = try_parse("succ (if true then false else true)").unwrap();
+        let (err, prefix) = eval_ast_verbose(input.clone()).unwrap_err();
+        assert!(matches!(
+            err,
+            ArithError::NotNumericError {
+                operator: "succ",
+                found: AST::False
+            }
+        ));
+        assert_eq!(prefix, vec![input, AST::Succ(Box::new(AST::False))]);
+
+        let input = try_parse("if iszero pred succ 0 then succ 0 else 0").unwrap();
+        assert_eq!(
+            eval_ast_verbose(input.clone()).unwrap(),
+            eval_ast(input).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_numeral_literals() {
+        let sixteen = (0..16).fold(AST::Zero, |v, _| AST::Succ(Box::new(v)));
+        assert_eq!(try_parse("16").unwrap(), sixteen);
+        assert_eq!(try_parse("0x10").unwrap(), sixteen);
+        assert_eq!(try_parse("1_6").unwrap(), sixteen);
+        assert_eq!(
+            try_parse("pred 0x1_F").unwrap(),
+            try_parse("pred 31").unwrap()
+        );
+        assert_eq!(
+            try_parse("iszero 0").unwrap(),
+            try_parse("iszero (0)").unwrap()
+        );
+        assert!(try_parse("1__6").is_err());
+        assert!(try_parse("16_").is_err());
+        assert!(matches!(
+            try_parse("99999999999999999999"),
+            Err(ArithError::NumeralTooLargeError(_))
+        ));
+        let max = eval(&format!("pred succ {}", MAX_NUMERAL)).unwrap();
+        assert_eq!(arith_size(&max), MAX_NUMERAL as u128 + 1);
+    }
+
+    #[test]
+    fn test_validate() {
+        let hand_built = arith!(if iszero 0 then succ 0 else pred false);
+        assert!(validate(&hand_built).is_ok());
+        assert!(validate(&try_parse("0x1000").unwrap()).is_ok());
+
+        let deep = (0..MAX_DEPTH).fold(AST::True, |v, _| AST::IsZero(Box::new(v)));
+        assert!(matches!(validate(&deep), Err(ArithError::TooDeepError)));
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let parens = |n| format!("{}0{}", "(".repeat(n), ")".repeat(n));
+        let ifs = |n| format!("{}0", "if true then 0 else ".repeat(n));
+        let succs = |n| format!("{}0", "succ ".repeat(n));
+        let ands = |n| format!("true{}", " and true".repeat(n));
+        assert_eq!(nesting("succ succ 0 and (pred 0) or iszero 0"), 3);
+        assert_eq!(nesting(&parens(64)), 64);
+        assert!(eval(&parens(64)).is_ok());
+        assert!(eval(&succs(257)).is_ok());
+        assert!(eval(&ands(257)).is_ok());
+        assert!(eval("4096").is_ok());
+        let deepest = MAX_DEPTH as usize - 1;
+        assert_eq!(
+            tapl_syntax::depth(&try_parse(&ifs(deepest)).unwrap()),
+            MAX_DEPTH as usize
+        );
+        assert!(eval(&ifs(deepest)).is_ok());
+
+        // Each of these used to overflow the stack before reaching the evaluator.
+        let too_deep = MAX_DEPTH as usize + 1;
+        for input in [
+            parens(too_deep),
+            ifs(too_deep),
+            succs(too_deep),
+            ands(too_deep),
+            "(".repeat(100_000),
+            "succsucc".repeat(10_000) + "0",
+            // Shallow source, but the numeral's `succ` chain takes the term past the limit.
+            format!(
+                "{}{}",
+                "succ ".repeat((MAX_DEPTH - MAX_NUMERAL as u128) as usize),
+                MAX_NUMERAL
+            ),
+        ] {
+            assert!(matches!(try_parse(&input), Err(ArithError::TooDeepError)));
+        }
+    }
+
+    #[test]
+    fn test_eval_stream() {
+        let program = "succ 0; iszero 0;\nif true then; pred 0;";
+        let mut results = eval_stream(program);
+        assert_eq!(
+            results.next().unwrap().unwrap(),
+            AST::Succ(Box::new(AST::Zero))
+        );
+        assert_eq!(results.next().unwrap().unwrap(), AST::True);
+        assert!(matches!(
+            results.next(),
+            Some(Err(ArithError::ParseError(_)))
+        ));
+        assert_eq!(results.next().unwrap().unwrap(), AST::Zero);
+        assert!(results.next().is_none());
+
+        let program = "0;\nsucc (";
+        let err = eval_stream(program).nth(1).unwrap().unwrap_err();
+        assert_eq!(err.to_diagnostic().labels[0].span.start, program.len());
+    }
+
+    #[test]
+    fn test_parse_recovering() {
+        let (last, errors) = try_parse_recovering("pred succ;\niszero 0");
+        assert_eq!(last, Some(AST::IsZero(Box::new(AST::Zero))));
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], ArithError::ParseError(_)));
+        assert_eq!(errors[0].to_diagnostic().labels[0].span.start, 9);
+
+        let (last, errors) = try_parse_recovering("succ; 0; if 0 then; 0x5000");
+        assert_eq!(last, Some(AST::Zero));
+        let offsets: Vec<_> = errors
+            .iter()
+            .map(|e| e.to_diagnostic().labels.first().map(|l| l.span.start))
+            .collect();
+        assert_eq!(offsets, [Some(4), Some(18), None]);
+        assert!(matches!(errors[2], ArithError::NumeralTooLargeError(_)));
+        assert_eq!(try_parse_recovering(" ; ").1.len(), 0);
+    }
+
+    #[test]
+    fn test_statements() {
+        let src = "é;\n succ 0 ; ;iszero";
+        let statements: Vec<_> = statements(src).collect();
+        assert_eq!(statements.len(), 3);
+        assert_eq!(statements[1].0, Span::new(5, 11));
+        assert_eq!(statements[1].1, "   \n succ 0 ");
+        assert_eq!(&src[statements[2].0.start..statements[2].0.end], "iszero");
+        let (_, errors) = try_parse_recovering(src);
+        assert_eq!(errors[1].to_diagnostic().labels[0].span.start, src.len());
+    }
+
+    #[test]
+    fn test_strict_numerals() {
+        let strict = EvalOptions {
+            strict_numerals: true,
+            ..EvalOptions::default()
+        };
+        let eval_with = |input, options| eval_ast_with(try_parse(input).unwrap(), options);
+        assert_eq!(eval("pred 0").unwrap(), AST::Zero);
+        assert_eq!(eval("pred pred 0").unwrap(), AST::Zero);
+        assert!(matches!(
+            eval_with("pred 0", &strict),
+            Err(ArithError::StuckTerm(AST::Pred(_)))
+        ));
+        assert!(matches!(
+            eval_with("pred pred 0", &strict),
+            Err(ArithError::StuckTerm(_))
+        ));
+        assert!(matches!(
+            eval_with("pred pred succ 0", &strict),
+            Err(ArithError::StuckTerm(_))
+        ));
+        assert_eq!(eval_with("pred succ 0", &strict).unwrap(), AST::Zero);
+        assert_eq!(
+            eval_with("if iszero pred succ 0 then 0 else pred 0", &strict).unwrap(),
+            AST::Zero
+        );
+    }
+
+    #[test]
+    fn test_eval_ast_traced() {
+        let input = try_parse("if iszero pred succ 0 then succ 0 else false").unwrap();
+        let (steps, result) = eval_ast_traced(input, &EvalOptions::default());
+        assert_eq!(result.unwrap(), AST::Succ(Box::new(AST::Zero)));
+        let summary: Vec<_> = steps
+            .iter()
+            .map(|step| {
+                (
+                    step.rule.name(),
+                    step.redex.as_slice(),
+                    step.term.to_string(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    "B-PredSucc",
+                    &[0, 0][..],
+                    "if iszero 0 then succ 0 else false".into()
+                ),
+                (
+                    "B-IsZeroZero",
+                    &[0],
+                    "if true then succ 0 else false".into()
+                ),
+                ("B-IfTrue", &[], "succ 0".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_modulus() {
+        let eval_mod = |input, m| {
+            let options = EvalOptions {
+                modulus: Some(m),
+                ..EvalOptions::default()
+            };
+            eval_ast_with(try_parse(input).unwrap(), &options).unwrap()
+        };
+        let numeral = |input| try_parse(input).unwrap();
+        assert_eq!(eval_mod("succ succ succ 0", 3), AST::Zero);
+        assert_eq!(eval_mod("succ succ 0", 3), numeral("2"));
+        assert_eq!(eval_mod("succ succ succ succ 0", 3), numeral("1"));
+        assert_eq!(eval_mod("pred 0x100", 256), AST::Zero);
+        assert_eq!(eval_mod("iszero succ 255", 256), AST::True);
+        assert_eq!(eval_mod("succ 0", 1), AST::Zero);
+        assert_eq!(eval_mod("succ 2", 0), numeral("3"));
+        assert_eq!(eval("succ succ succ 0").unwrap(), numeral("3"));
+    }
+
+    #[test]
+    fn test_small_step() {
+        let steps: Vec<AST> = std::iter::successors(
+            Some(try_parse("if iszero pred 1 then succ (if true then 0 else 1) else 0").unwrap()),
+            |v| eval1(v.clone()).ok(),
+        )
+        .collect();
+        let expected = [
+            "if iszero pred 1 then succ (if true then 0 else 1) else 0",
+            "if iszero 0 then succ (if true then 0 else 1) else 0",
+            "if true then succ (if true then 0 else 1) else 0",
+            "succ (if true then 0 else 1)",
+            "succ 0",
+        ]
+        .map(|input| try_parse(input).unwrap());
+        assert_eq!(steps, expected);
+        assert!(matches!(
+            eval1(try_parse("succ true").unwrap()),
+            Err(ArithError::NotNumericError {
+                operator: "succ",
+                ..
+            })
+        ));
+
+        for v in gen::enumerate_arith(6) {
+            let small = eval_small(v.clone()).map_err(|e| e.to_string());
+            let big = eval_ast(v.clone()).map_err(|e| e.to_string());
+            assert_eq!(small, big, "{}", v);
+        }
+    }
+
+    #[test]
+    fn test_small_step_options() {
+        let modulus = EvalOptions {
+            modulus: Some(2),
+            ..EvalOptions::default()
+        };
+        let strict = EvalOptions {
+            strict_numerals: true,
+            ..EvalOptions::default()
+        };
+        let steps = eval_small_traced(try_parse("iszero succ succ 0").unwrap(), &modulus).0;
+        let rules: Vec<_> = steps.iter().map(|step| step.rule.name()).collect();
+        assert_eq!(rules, ["E-Wrap", "E-IsZeroZero"]);
+        assert!(matches!(
+            eval_small_with(try_parse("succ pred 0").unwrap(), &strict),
+            Err(ArithError::StuckTerm(_))
+        ));
+
+        for options in [modulus, strict] {
+            for v in gen::enumerate_arith(6) {
+                let small = eval_small_with(v.clone(), &options).map_err(|e| e.to_string());
+                let big = eval_ast_with(v.clone(), &options).map_err(|e| e.to_string());
+                assert_eq!(small, big, "{}", v);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reductions() {
+        let chain = (0..200).fold(try_parse("5").unwrap(), |v, _| AST::Pred(Box::new(v)));
+        let first: Vec<_> = reductions(chain).take(3).collect();
+        let expected = (0..197).fold(try_parse("2").unwrap(), |v, _| AST::Pred(Box::new(v)));
+        assert_eq!(first.len(), 3);
+        assert_eq!(first[2], expected);
+
+        let v = try_parse("if iszero pred 1 then succ (if true then 0 else 1) else 0").unwrap();
+        assert_eq!(
+            reductions(v).find(|v| !matches!(v, AST::IfThenElse(..))),
+            Some(try_parse("succ (if true then 0 else 1)").unwrap())
+        );
+        let stuck: Vec<_> = reductions(try_parse("succ iszero 0").unwrap()).collect();
+        assert_eq!(stuck, [try_parse("succ true").unwrap()]);
+        assert_eq!(reductions(AST::Zero).next(), None);
+    }
+
+    #[test]
+    fn test_try_eval() {
+        let variants = [None, Some(0), Some(1), Some(3)].map(|modulus| EvalOptions {
+            modulus,
+            strict_numerals: modulus == Some(3),
+        });
+        let mut g = gen::Gen::new(199, 40);
+        for _ in 0..500 {
+            let v = gen::sized_term(&mut g);
+            for options in &variants {
+                let expected = eval_ast_with(v.clone(), options).ok();
+                assert_eq!(try_eval(v.clone(), options).ok(), expected, "{}", v);
+            }
+        }
+
+        let chain = |n| (0..n).fold(AST::Zero, |v, _| AST::Pred(Box::new(v)));
+        // Too deep for a test thread's stack, but not for the evaluation thread's.
+        let deep = chain(MAX_DEPTH as usize - 1);
+        assert_eq!(try_eval(deep, &EvalOptions::default()).unwrap(), AST::Zero);
+        // Rejected, and freed, without recursing at all.
+        let deeper = chain(1_000_000);
+        assert!(matches!(
+            try_eval(deeper, &EvalOptions::default()),
+            Err(ArithError::TooDeepError)
+        ));
+    }
+
+    #[test]
+    fn test_try_eval_str() {
+        assert_eq!(
+            try_eval_str("if iszero pred 1 then succ 0 else 0"),
+            Ok("succ 0".to_owned())
+        );
+        let nested = format!("{}0", "if true then 0 else ".repeat(MAX_DEPTH as usize - 1));
+        assert_eq!(try_eval_str(&nested), Ok("0".to_owned()));
+        for src in [
+            String::new(),
+            "succ true".to_owned(),
+            "0xffffffffffffffffffffffff".to_owned(),
+            "(".repeat(100_000),
+            "if".repeat(100_000),
+            "é; \0 )".to_owned(),
+        ] {
+            assert!(try_eval_str(&src).is_err(), "{}", src);
+        }
+    }
+
+    #[test]
+    fn test_iszero_eval() {
+        let input = try_parse("if true then 0 else succ 0").unwrap();
+        assert_eq!(iszero_eval(input).unwrap(), AST::True);
+        let input = try_parse("pred succ succ 0").unwrap();
+        assert_eq!(iszero_eval(input).unwrap(), AST::False);
+        assert!(matches!(
+            iszero_eval(AST::True),
+            Err(ArithError::NotNumericError { .. })
+        ));
+        assert_eq!(
+            eval("iszero (if true then 0 else succ 0)").unwrap(),
+            AST::True
+        );
+    }
+
+    #[test]
+    fn test_cmp_numeric() {
+        use std::cmp::Ordering;
+        let one = try_parse("succ 0").unwrap();
+        let two = try_parse("succ succ 0").unwrap();
+        assert_eq!(cmp_numeric(&one, &two).unwrap(), Some(Ordering::Less));
+        assert_eq!(cmp_numeric(&two, &one).unwrap(), Some(Ordering::Greater));
+        let also_one = try_parse("pred if true then 2 else 0").unwrap();
+        assert_eq!(cmp_numeric(&one, &also_one).unwrap(), Some(Ordering::Equal));
+        assert_eq!(cmp_numeric(&one, &AST::True).unwrap(), None);
+        assert!(cmp_numeric(&one, &try_parse("succ false").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_diagnostic() {
+        use tapl_diagnostics::{Renderer, SourceFile};
+        let input = "pred succ";
+        let diagnostic = try_parse(input).unwrap_err().to_diagnostic();
+        assert_eq!(
+            Renderer::plain().render(&diagnostic, &SourceFile::new("<stdin>", input)),
+            "error: failed to parse input\n --> <stdin>:1:10\n  |\n1 | pred succ\n  |          ^ expected IfThenElse, Pred, Succ, IsZero, Not, True, False, Zero, or Numeral"
+        );
+    }
+
+    #[test]
+    fn test_error_source() {
+        use std::error::Error;
+        let err = try_parse("succ").unwrap_err();
+        let source = err.source().expect("parse errors should carry a source");
+        assert!(source.downcast_ref::<pest::error::Error<Rule>>().is_some());
+
+        let err = eval_ast(try_parse("succ true").unwrap()).unwrap_err();
+        assert!(err.source().is_none());
+    }
+}
+
+tapl_syntax::term_like_tests!(
+    term_like,
+    AST,
+    ["true", "0", "pred 0", "if iszero 0 then succ 0 else false"]
+        .map(|input| try_parse(input).unwrap())
+        .into()
+);
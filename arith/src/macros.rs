@@ -0,0 +1,142 @@
+/// Builds an `AST` from surface syntax written inline, sugar included:
+/// `arith!(if iszero (succ 0) then 1 else not true and false)`.
+///
+/// The expansion follows the grammar exactly. Prefix operators take one operand, `and`
+/// binds tighter than `or`, both fold to the left, and `else` takes the rest of the
+/// enclosing term. Numerals are integer literals, so `0x10` and `1_000` work too.
+#[macro_export]
+macro_rules! arith {
+    // `@term [or] [and] [prefix operators] tokens` reads one operand; a pending
+    // disjunction and conjunction ride along, each a single parenthesised expression.
+    (@term [$($or:tt)?] [$($and:tt)?] [$($pre:ident)*] succ $($rest:tt)*) => {
+        $crate::arith!(@term [$($or)?] [$($and)?] [$($pre)* Succ] $($rest)*)
+    };
+    (@term [$($or:tt)?] [$($and:tt)?] [$($pre:ident)*] pred $($rest:tt)*) => {
+        $crate::arith!(@term [$($or)?] [$($and)?] [$($pre)* Pred] $($rest)*)
+    };
+    (@term [$($or:tt)?] [$($and:tt)?] [$($pre:ident)*] iszero $($rest:tt)*) => {
+        $crate::arith!(@term [$($or)?] [$($and)?] [$($pre)* IsZero] $($rest)*)
+    };
+    (@term [$($or:tt)?] [$($and:tt)?] [$($pre:ident)*] not $($rest:tt)*) => {
+        $crate::arith!(@term [$($or)?] [$($and)?] [$($pre)* Not] $($rest)*)
+    };
+    (@term [$($or:tt)?] [$($and:tt)?] [$($pre:ident)*] true $($rest:tt)*) => {
+        $crate::arith!(@and [$($or)?] [$($and)?]
+            ($crate::arith!(@prefix [$($pre)*] $crate::surface::SurfaceAST::True)) $($rest)*)
+    };
+    (@term [$($or:tt)?] [$($and:tt)?] [$($pre:ident)*] false $($rest:tt)*) => {
+        $crate::arith!(@and [$($or)?] [$($and)?]
+            ($crate::arith!(@prefix [$($pre)*] $crate::surface::SurfaceAST::False)) $($rest)*)
+    };
+    (@term [$($or:tt)?] [$($and:tt)?] [$($pre:ident)*] ($($inner:tt)+) $($rest:tt)*) => {
+        $crate::arith!(@and [$($or)?] [$($and)?]
+            ($crate::arith!(@prefix [$($pre)*] $crate::arith!(@term [] [] [] $($inner)+)))
+            $($rest)*)
+    };
+    (@term [$($or:tt)?] [$($and:tt)?] [$($pre:ident)*] $n:literal $($rest:tt)*) => {
+        $crate::arith!(@and [$($or)?] [$($and)?]
+            ($crate::arith!(@prefix [$($pre)*] $crate::surface::SurfaceAST::Numeral($n)))
+            $($rest)*)
+    };
+    (@term [$($or:tt)?] [$($and:tt)?] [$($pre:ident)*] if $($rest:tt)*) => {
+        $crate::arith!(@cond [$($or)?] [$($and)?] [$($pre)*] [] [] $($rest)*)
+    };
+
+    // The condition runs to the `then` that isn't claimed by an `if` nested inside it;
+    // each nested `if` is pushed on the depth list and popped at its own `else`.
+    (@cond $or:tt $and:tt $pre:tt [] [$($c:tt)*] then $($rest:tt)*) => {
+        $crate::arith!(@then $or $and $pre [$($c)*] [] [] $($rest)*)
+    };
+    (@cond $or:tt $and:tt $pre:tt [$($d:tt)*] [$($c:tt)*] if $($rest:tt)*) => {
+        $crate::arith!(@cond $or $and $pre [if $($d)*] [$($c)* if] $($rest)*)
+    };
+    (@cond $or:tt $and:tt $pre:tt [$d:tt $($ds:tt)*] [$($c:tt)*] else $($rest:tt)*) => {
+        $crate::arith!(@cond $or $and $pre [$($ds)*] [$($c)* else] $($rest)*)
+    };
+    (@cond $or:tt $and:tt $pre:tt $d:tt [$($c:tt)*] $t:tt $($rest:tt)*) => {
+        $crate::arith!(@cond $or $and $pre $d [$($c)* $t] $($rest)*)
+    };
+
+    // Likewise the `then` branch runs to its own `else`, and the rest is the `else` branch.
+    (@then [$($or:tt)?] [$($and:tt)?] [$($pre:ident)*] [$($c:tt)*] [] [$($t:tt)*]
+        else $($rest:tt)*) => {
+        $crate::arith!(@and [$($or)?] [$($and)?]
+            ($crate::arith!(@prefix [$($pre)*] $crate::surface::SurfaceAST::IfThenElse(
+                Box::new($crate::arith!(@term [] [] [] $($c)*)),
+                Box::new($crate::arith!(@term [] [] [] $($t)*)),
+                Box::new($crate::arith!(@term [] [] [] $($rest)*)),
+            ))))
+    };
+    (@then $or:tt $and:tt $pre:tt $c:tt [$($d:tt)*] [$($t:tt)*] if $($rest:tt)*) => {
+        $crate::arith!(@then $or $and $pre $c [if $($d)*] [$($t)* if] $($rest)*)
+    };
+    (@then $or:tt $and:tt $pre:tt $c:tt [$d:tt $($ds:tt)*] [$($t:tt)*] else $($rest:tt)*) => {
+        $crate::arith!(@then $or $and $pre $c [$($ds)*] [$($t)* else] $($rest)*)
+    };
+    (@then $or:tt $and:tt $pre:tt $c:tt $d:tt [$($t:tt)*] $x:tt $($rest:tt)*) => {
+        $crate::arith!(@then $or $and $pre $c $d [$($t)* $x] $($rest)*)
+    };
+
+    // Prefix operators were collected outermost first.
+    (@prefix [] $e:expr) => { $e };
+    (@prefix [$op:ident $($pre:ident)*] $e:expr) => {
+        $crate::surface::SurfaceAST::$op(Box::new($crate::arith!(@prefix [$($pre)*] $e)))
+    };
+
+    // An operand just ended: fold it into the conjunction, then see what follows.
+    (@and [$($or:tt)?] [] $e:tt $($rest:tt)*) => {
+        $crate::arith!(@after [$($or)?] [$e] $($rest)*)
+    };
+    (@and [$($or:tt)?] [$and:tt] $e:tt $($rest:tt)*) => {
+        $crate::arith!(@after [$($or)?]
+            [($crate::surface::SurfaceAST::And(Box::new($and), Box::new($e)))] $($rest)*)
+    };
+    (@after [$($or:tt)?] [$and:tt] and $($rest:tt)+) => {
+        $crate::arith!(@term [$($or)?] [$and] [] $($rest)+)
+    };
+    (@after [] [$and:tt] or $($rest:tt)+) => {
+        $crate::arith!(@term [$and] [] [] $($rest)+)
+    };
+    (@after [$or:tt] [$and:tt] or $($rest:tt)+) => {
+        $crate::arith!(@term [($crate::surface::SurfaceAST::Or(Box::new($or), Box::new($and)))]
+            [] [] $($rest)+)
+    };
+    (@after [] [$and:tt]) => { $and };
+    (@after [$or:tt] [$and:tt]) => {
+        $crate::surface::SurfaceAST::Or(Box::new($or), Box::new($and))
+    };
+
+    ($($t:tt)+) => {
+        $crate::surface::desugar($crate::arith!(@term [] [] [] $($t)+))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{try_parse, AST};
+
+    #[test]
+    fn test_arith_macro() {
+        assert_eq!(arith!(0), AST::Zero);
+        assert_eq!(arith!(succ pred 0), try_parse("succ pred 0").unwrap());
+        assert_eq!(
+            arith!(if iszero (succ 0) then true else false),
+            try_parse("if iszero (succ 0) then true else false").unwrap()
+        );
+        assert_eq!(
+            arith!(succ 0x2 and not true or false and false or 1_0),
+            try_parse("succ 0x2 and not true or false and false or 1_0").unwrap()
+        );
+        assert_eq!(
+            arith!(if if true then false else true then if false then 0 else 1 else 2 and true),
+            try_parse(
+                "if if true then false else true then if false then 0 else 1 else 2 and true"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            arith!(iszero if (true or false) then 3 else pred 1 and true),
+            try_parse("iszero if (true or false) then 3 else pred 1 and true").unwrap()
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use std::{fmt::Write, str::FromStr};
+
+use serde_json::{json, Value};
+
+use tapl_syntax::TermLike;
+
+use crate::{is_numeric_val, numeral_value, AST};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Format {
+    #[default]
+    Text,
+    Json,
+    Sexpr,
+    Dot,
+    Tree,
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "sexpr" => Ok(Format::Sexpr),
+            "dot" => Ok(Format::Dot),
+            "tree" => Ok(Format::Tree),
+            _ => Err(format!("unknown output format `{}`", s)),
+        }
+    }
+}
+
+/// Externally tagged: literals are strings, e.g. `{"Succ":"Zero"}`,
+/// `{"IfThenElse":["True","Zero",{"Pred":"Zero"}]}`. Numeric values other than `0` are
+/// written flat, as `{"Numeral":200}`, so large numbers don't nest past what JSON
+/// readers will accept.
+pub fn to_json(v: &AST) -> Value {
+    match v {
+        AST::True => json!("True"),
+        AST::False => json!("False"),
+        AST::Zero => json!("Zero"),
+        v if is_numeric_val(v) => json!({ "Numeral": numeral_value(v).unwrap() as u64 }),
+        AST::Succ(v) => json!({ "Succ": to_json(v) }),
+        AST::Pred(v) => json!({ "Pred": to_json(v) }),
+        AST::IsZero(v) => json!({ "IsZero": to_json(v) }),
+        AST::IfThenElse(cond, then, els) => {
+            json!({ "IfThenElse": [to_json(cond), to_json(then), to_json(els)] })
+        }
+    }
+}
+
+pub fn to_sexpr(v: &AST) -> String {
+    match v.children().as_slice() {
+        [] => v.label(),
+        children => {
+            let children: Vec<_> = children.iter().map(|v| to_sexpr(v)).collect();
+            format!("({} {})", v.label(), children.join(" "))
+        }
+    }
+}
+
+pub fn to_dot(v: &AST) -> String {
+    tapl_syntax::to_dot(v, "AST")
+}
+
+/// Branch, last branch and continuation prefixes of the tree view.
+type Glyphs = [&'static str; 3];
+
+const UNICODE_GLYPHS: Glyphs = ["├─ ", "└─ ", "│  "];
+const ASCII_GLYPHS: Glyphs = ["|- ", "+- ", "|  "];
+
+fn write_tree(out: &mut String, v: &AST, prefix: &str, glyphs: &Glyphs) {
+    out.push_str(&v.label());
+    let children = v.children();
+    for (i, child) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        let (branch, indent) = if last {
+            (glyphs[1], "   ")
+        } else {
+            (glyphs[0], glyphs[2])
+        };
+        write!(out, "\n{}{}", prefix, branch).unwrap();
+        write_tree(out, child, &format!("{}{}", prefix, indent), glyphs);
+    }
+}
+
+pub fn to_tree(v: &AST) -> String {
+    let mut out = String::new();
+    write_tree(&mut out, v, "", &UNICODE_GLYPHS);
+    out
+}
+
+/// Like `to_tree`, but drawn with plain ASCII for terminals without UTF-8.
+pub fn to_tree_ascii(v: &AST) -> String {
+    let mut out = String::new();
+    write_tree(&mut out, v, "", &ASCII_GLYPHS);
+    out
+}
+
+pub fn render(v: &AST, format: Format) -> String {
+    match format {
+        Format::Text => v.to_string(),
+        Format::Json => to_json(v).to_string(),
+        Format::Sexpr => to_sexpr(v),
+        Format::Dot => to_dot(v),
+        Format::Tree => to_tree(v),
+    }
+}
+
+/// Like `render`, but guaranteed to produce only ASCII.
+pub fn render_ascii(v: &AST, format: Format) -> String {
+    match format {
+        Format::Tree => to_tree_ascii(v),
+        format => render(v, format),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::try_parse;
+
+    #[test]
+    fn test_render_formats() {
+        let input = try_parse("if iszero 0 then succ 0 else false").unwrap();
+        assert_eq!(
+            render(&input, Format::Text),
+            "if iszero 0 then succ 0 else false"
+        );
+        let json: Value = serde_json::from_str(&render(&input, Format::Json)).unwrap();
+        assert_eq!(
+            json,
+            json!({ "IfThenElse": [{ "IsZero": "Zero" }, { "Numeral": 1 }, "False"] })
+        );
+        let numeral = try_parse("succ pred 200").unwrap();
+        assert_eq!(
+            to_json(&numeral),
+            json!({ "Succ": { "Pred": { "Numeral": 200 } } })
+        );
+        assert_eq!(
+            render(&input, Format::Sexpr),
+            "(if (iszero 0) (succ 0) false)"
+        );
+        let dot = render(&input, Format::Dot);
+        assert!(dot.starts_with("digraph AST {") && dot.ends_with('}'));
+        assert_eq!(dot.matches("->").count(), 5);
+        assert_eq!(
+            render(&input, Format::Tree),
+            "if\n├─ iszero\n│  └─ 0\n├─ succ\n│  └─ 0\n└─ false"
+        );
+        assert_eq!(
+            render_ascii(&input, Format::Tree),
+            "if\n|- iszero\n|  +- 0\n|- succ\n|  +- 0\n+- false"
+        );
+        assert_eq!("dot".parse(), Ok(Format::Dot));
+        assert!("xml".parse::<Format>().is_err());
+    }
+}
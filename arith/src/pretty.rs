@@ -0,0 +1,106 @@
+use tapl_pretty::with_prec;
+pub use tapl_pretty::{render, render_highlights, render_to, Doc, Target};
+
+use crate::AST;
+
+const INDENT: usize = 2;
+
+// `if` only parses as a whole term; the prefix operators also take operands.
+const IF_PREC: u8 = 0;
+const OPERAND_PREC: u8 = 1;
+
+fn prec(v: &AST) -> u8 {
+    match v {
+        AST::IfThenElse(..) => IF_PREC,
+        _ => OPERAND_PREC,
+    }
+}
+
+fn child(v: &AST, outer: u8, highlight: Option<&[usize]>, index: usize) -> Doc {
+    let highlight = match highlight {
+        Some([i, rest @ ..]) if *i == index => Some(rest),
+        _ => None,
+    };
+    with_prec(outer, prec(v), to_doc_highlighting(v, highlight))
+}
+
+/// Like `to_doc`, highlighting the subterm reached by the child indices in `highlight`.
+pub fn to_doc_highlighting(v: &AST, highlight: Option<&[usize]>) -> Doc {
+    let operand = |v, prefix: &str| Doc::text(prefix).concat(child(v, OPERAND_PREC, highlight, 0));
+    let doc = match v {
+        AST::True => Doc::text("true"),
+        AST::False => Doc::text("false"),
+        AST::Zero => Doc::text("0"),
+        AST::Succ(v) => operand(v, "succ "),
+        AST::Pred(v) => operand(v, "pred "),
+        AST::IsZero(v) => operand(v, "iszero "),
+        AST::IfThenElse(cond, then, els) => Doc::text("if ")
+            .concat(child(cond, IF_PREC, highlight, 0).nest(INDENT))
+            .concat(
+                Doc::Line
+                    .concat(Doc::text("then "))
+                    .concat(child(then, IF_PREC, highlight, 1))
+                    .concat(Doc::Line)
+                    .concat(Doc::text("else "))
+                    .concat(child(els, IF_PREC, highlight, 2))
+                    .nest(INDENT),
+            )
+            .group(),
+    };
+    match highlight {
+        Some([]) => doc.highlight(),
+        _ => doc,
+    }
+}
+
+pub fn to_doc(v: &AST) -> Doc {
+    to_doc_highlighting(v, None)
+}
+
+/// Lays `v` out in surface syntax, breaking `if` expressions across lines
+/// whenever they would not fit in `width` columns.
+pub fn pretty(v: &AST, width: usize) -> String {
+    render(&to_doc(v), width)
+}
+
+/// Like `pretty`, for `target`, with the subterm at `path` highlighted.
+pub fn pretty_highlighted(v: &AST, path: &[usize], width: usize, target: Target) -> String {
+    render_to(&to_doc_highlighting(v, Some(path)), width, target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::try_parse;
+
+    #[test]
+    fn test_pretty_round_trip() {
+        let input = try_parse(
+            "if iszero (if true then 0 else succ 0) then succ (if false then 0 else 0) else pred 0",
+        )
+        .unwrap();
+        for width in [0, 20, 40, 80, usize::MAX] {
+            assert_eq!(try_parse(&pretty(&input, width)).unwrap(), input);
+        }
+    }
+
+    #[test]
+    fn test_pretty_highlighted() {
+        let input = try_parse("if iszero pred 0 then succ (if true then 0 else 0) else 0").unwrap();
+        let ansi = pretty_highlighted(&input, &[1, 0], usize::MAX, Target::Ansi);
+        assert_eq!(
+            ansi,
+            "if iszero pred 0 then succ (\x1b[1;4mif true then 0 else 0\x1b[0m) else 0"
+        );
+        let latex = pretty_highlighted(&input, &[0, 0], 40, Target::Latex);
+        assert_eq!(
+            latex,
+            "if iszero \\textbf{pred 0}\n  then succ (if true then 0 else 0)\n  else 0"
+        );
+        // A path past the leaves highlights nothing.
+        assert_eq!(
+            pretty_highlighted(&input, &[2, 0], usize::MAX, Target::Plain),
+            input.to_string()
+        );
+    }
+}
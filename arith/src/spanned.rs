@@ -0,0 +1,221 @@
+use tapl_syntax::TermLike;
+
+use crate::{
+    surface::{SpanTree, SurfaceAST},
+    AST,
+};
+
+/// A node together with the bytes `span.0..span.1` of the source it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: (usize, usize),
+}
+
+/// The core `AST` with every child spanned. Nodes that desugaring introduces take the span
+/// of the sugar they come from, so each `succ` of a numeral points at the numeral.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Node {
+    True,
+    False,
+    Zero,
+    Succ(Box<Spanned<Node>>),
+    Pred(Box<Spanned<Node>>),
+    IsZero(Box<Spanned<Node>>),
+    IfThenElse(Box<Spanned<Node>>, Box<Spanned<Node>>, Box<Spanned<Node>>),
+}
+
+/// Built nodes span their children, or nothing at offset 0 if they have none.
+impl TermLike for Spanned<Node> {
+    fn children(&self) -> Vec<&Self> {
+        match &self.node {
+            Node::True | Node::False | Node::Zero => vec![],
+            Node::Succ(v) | Node::Pred(v) | Node::IsZero(v) => vec![v],
+            Node::IfThenElse(cond, then, els) => vec![cond, then, els],
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Self> {
+        match &mut self.node {
+            Node::True | Node::False | Node::Zero => vec![],
+            Node::Succ(v) | Node::Pred(v) | Node::IsZero(v) => vec![v],
+            Node::IfThenElse(cond, then, els) => vec![cond, then, els],
+        }
+    }
+
+    fn label(&self) -> String {
+        let label = match self.node {
+            Node::True => "true",
+            Node::False => "false",
+            Node::Zero => "0",
+            Node::Succ(_) => "succ",
+            Node::Pred(_) => "pred",
+            Node::IsZero(_) => "iszero",
+            Node::IfThenElse(..) => "if",
+        };
+        label.to_owned()
+    }
+
+    fn from_parts(label: &str, children: Vec<Self>) -> Option<Self> {
+        let span = match (children.first(), children.last()) {
+            (Some(first), Some(last)) => (first.span.0, last.span.1),
+            _ => (0, 0),
+        };
+        let arity = children.len();
+        let mut children = children.into_iter().map(Box::new);
+        let mut next = || children.next().unwrap();
+        let node = match (label, arity) {
+            ("true", 0) => Node::True,
+            ("false", 0) => Node::False,
+            ("0", 0) => Node::Zero,
+            ("succ", 1) => Node::Succ(next()),
+            ("pred", 1) => Node::Pred(next()),
+            ("iszero", 1) => Node::IsZero(next()),
+            ("if", 3) => Node::IfThenElse(next(), next(), next()),
+            _ => return None,
+        };
+        Some(Spanned { node, span })
+    }
+}
+
+impl From<Spanned<Node>> for AST {
+    fn from(v: Spanned<Node>) -> Self {
+        let strip = |v: Box<Spanned<Node>>| Box::new(AST::from(*v));
+        match v.node {
+            Node::True => AST::True,
+            Node::False => AST::False,
+            Node::Zero => AST::Zero,
+            Node::Succ(v) => AST::Succ(strip(v)),
+            Node::Pred(v) => AST::Pred(strip(v)),
+            Node::IsZero(v) => AST::IsZero(strip(v)),
+            Node::IfThenElse(cond, then, els) => {
+                AST::IfThenElse(strip(cond), strip(then), strip(els))
+            }
+        }
+    }
+}
+
+// What is left to do for a node of `desugar_spanned`, each assembly step carrying the span
+// of the sugar it builds.
+enum Task<'a> {
+    Expand(SurfaceAST, &'a SpanTree),
+    Succ((usize, usize)),
+    Pred((usize, usize)),
+    IsZero((usize, usize)),
+    If((usize, usize)),
+    Not((usize, usize)),
+    And((usize, usize)),
+    Or((usize, usize)),
+}
+
+// Mirrors `surface::desugar`, reading each node's span off the matching `SpanTree` node.
+pub(crate) fn desugar_spanned(t: SurfaceAST, spans: &SpanTree) -> Spanned<Node> {
+    let mut tasks = vec![Task::Expand(t, spans)];
+    let mut done: Vec<Spanned<Node>> = Vec::new();
+    while let Some(task) = tasks.pop() {
+        let mut pop = || Box::new(done.pop().unwrap());
+        let (node, span) = match task {
+            Task::Expand(t, spans) => {
+                let span = (spans.span.start, spans.span.end);
+                let at = |node| Spanned { node, span };
+                let child = |i: usize| &spans.children[i];
+                // Children are pushed last first, so they come out done in order.
+                match t {
+                    SurfaceAST::True => done.push(at(Node::True)),
+                    SurfaceAST::False => done.push(at(Node::False)),
+                    SurfaceAST::Zero => done.push(at(Node::Zero)),
+                    SurfaceAST::Numeral(n) => {
+                        done.push((0..n).fold(at(Node::Zero), |v, _| at(Node::Succ(Box::new(v)))))
+                    }
+                    SurfaceAST::Succ(t) => {
+                        tasks.extend([Task::Succ(span), Task::Expand(*t, child(0))])
+                    }
+                    SurfaceAST::Pred(t) => {
+                        tasks.extend([Task::Pred(span), Task::Expand(*t, child(0))])
+                    }
+                    SurfaceAST::IsZero(t) => {
+                        tasks.extend([Task::IsZero(span), Task::Expand(*t, child(0))])
+                    }
+                    SurfaceAST::Not(t) => {
+                        tasks.extend([Task::Not(span), Task::Expand(*t, child(0))])
+                    }
+                    SurfaceAST::IfThenElse(cond, then, els) => tasks.extend([
+                        Task::If(span),
+                        Task::Expand(*els, child(2)),
+                        Task::Expand(*then, child(1)),
+                        Task::Expand(*cond, child(0)),
+                    ]),
+                    SurfaceAST::And(a, b) => tasks.extend([
+                        Task::And(span),
+                        Task::Expand(*b, child(1)),
+                        Task::Expand(*a, child(0)),
+                    ]),
+                    SurfaceAST::Or(a, b) => tasks.extend([
+                        Task::Or(span),
+                        Task::Expand(*b, child(1)),
+                        Task::Expand(*a, child(0)),
+                    ]),
+                }
+                continue;
+            }
+            Task::Succ(span) => (Node::Succ(pop()), span),
+            Task::Pred(span) => (Node::Pred(pop()), span),
+            Task::IsZero(span) => (Node::IsZero(pop()), span),
+            Task::If(span) => {
+                let (els, then) = (pop(), pop());
+                (Node::IfThenElse(pop(), then, els), span)
+            }
+            Task::Not(span) => {
+                let at = |node| Box::new(Spanned { node, span });
+                (
+                    Node::IfThenElse(pop(), at(Node::False), at(Node::True)),
+                    span,
+                )
+            }
+            Task::And(span) => {
+                let b = pop();
+                let at = |node| Box::new(Spanned { node, span });
+                (Node::IfThenElse(pop(), b, at(Node::False)), span)
+            }
+            Task::Or(span) => {
+                let b = pop();
+                let at = |node| Box::new(Spanned { node, span });
+                (Node::IfThenElse(pop(), at(Node::True), b), span)
+            }
+        };
+        done.push(Spanned { node, span });
+    }
+    done.pop().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{try_parse, try_parse_spanned};
+
+    #[test]
+    fn test_try_parse_spanned() {
+        let input = "if iszero pred 2 then succ 0 else false or true";
+        let spanned = try_parse_spanned(input).unwrap();
+        assert_eq!(spanned.span, (0, input.len()));
+        let text = |path: &[usize]| {
+            let (start, end) = tapl_syntax::get(&spanned, path).unwrap().span;
+            &input[start..end]
+        };
+        assert_eq!(text(&[0]), "iszero pred 2");
+        assert_eq!(text(&[0, 0, 0, 0]), "2");
+        assert_eq!(text(&[2]), "false or true");
+        assert_eq!(text(&[2, 1]), "false or true");
+        assert_eq!(text(&[2, 2]), "true");
+        assert!(tapl_syntax::get(&spanned, &[1, 0, 0]).is_none());
+        assert_eq!(AST::from(spanned), try_parse(input).unwrap());
+    }
+}
+
+tapl_syntax::term_like_tests!(
+    term_like,
+    Spanned<Node>,
+    ["true", "1", "if iszero 0 then succ 0 else false and true"]
+        .map(|input| crate::try_parse_spanned(input).unwrap())
+        .into()
+);
@@ -0,0 +1,407 @@
+use pest::iterators::Pair;
+use tapl_diagnostics::Span;
+use tapl_syntax::TermLike;
+
+use crate::{ArithError, Rule, TryTake, AST, MAX_NUMERAL};
+
+/// The term as written, before sugar is expanded into the core `AST`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SurfaceAST {
+    True,
+    False,
+    Zero,
+    Succ(Box<SurfaceAST>),
+    Pred(Box<SurfaceAST>),
+    IsZero(Box<SurfaceAST>),
+    IfThenElse(Box<SurfaceAST>, Box<SurfaceAST>, Box<SurfaceAST>),
+    Numeral(u64),
+    Not(Box<SurfaceAST>),
+    And(Box<SurfaceAST>, Box<SurfaceAST>),
+    Or(Box<SurfaceAST>, Box<SurfaceAST>),
+}
+
+fn numeral(literal: &str) -> Result<SurfaceAST, ArithError> {
+    let digits = literal.replace('_', "");
+    let value = match digits.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => digits.parse(),
+    };
+    match value {
+        Ok(n) if n <= MAX_NUMERAL => Ok(SurfaceAST::Numeral(n)),
+        _ => Err(ArithError::NumeralTooLargeError(literal.to_owned())),
+    }
+}
+
+// Folds `operand (op operand)*` to the left.
+fn infix(
+    value: Pair<'_, Rule>,
+    op: fn(Box<SurfaceAST>, Box<SurfaceAST>) -> SurfaceAST,
+) -> Result<SurfaceAST, ArithError> {
+    let mut pairs = value.into_inner();
+    let first = pairs.try_take()?.try_into()?;
+    pairs.try_fold(first, |lhs, rhs| {
+        Ok(op(Box::new(lhs), Box::new(rhs.try_into()?)))
+    })
+}
+
+impl TryFrom<Pair<'_, Rule>> for SurfaceAST {
+    type Error = ArithError;
+    fn try_from(value: Pair<'_, Rule>) -> Result<Self, Self::Error> {
+        match value.as_rule() {
+            Rule::Term => infix(value, SurfaceAST::Or),
+            Rule::Conjunction => infix(value, SurfaceAST::And),
+            Rule::True => Ok(SurfaceAST::True),
+            Rule::False => Ok(SurfaceAST::False),
+            Rule::Zero => Ok(SurfaceAST::Zero),
+            Rule::Numeral => numeral(value.as_str()),
+            Rule::Succ => {
+                let mut pairs = value.into_inner();
+                Ok(SurfaceAST::Succ(Box::new(pairs.try_take()?.try_into()?)))
+            }
+            Rule::Pred => {
+                let mut pairs = value.into_inner();
+                Ok(SurfaceAST::Pred(Box::new(pairs.try_take()?.try_into()?)))
+            }
+            Rule::IsZero => {
+                let mut pairs = value.into_inner();
+                Ok(SurfaceAST::IsZero(Box::new(pairs.try_take()?.try_into()?)))
+            }
+            Rule::Not => {
+                let mut pairs = value.into_inner();
+                Ok(SurfaceAST::Not(Box::new(pairs.try_take()?.try_into()?)))
+            }
+            Rule::IfThenElse => {
+                let mut pairs = value.into_inner();
+                Ok(SurfaceAST::IfThenElse(
+                    Box::new(pairs.try_take()?.try_into()?),
+                    Box::new(pairs.try_take()?.try_into()?),
+                    Box::new(pairs.try_take()?.try_into()?),
+                ))
+            }
+            _ => Err(ArithError::UnexpectedNodeError(value.as_rule())),
+        }
+    }
+}
+
+impl TermLike for SurfaceAST {
+    fn children(&self) -> Vec<&Self> {
+        match self {
+            SurfaceAST::True | SurfaceAST::False | SurfaceAST::Zero | SurfaceAST::Numeral(_) => {
+                vec![]
+            }
+            SurfaceAST::Succ(v)
+            | SurfaceAST::Pred(v)
+            | SurfaceAST::IsZero(v)
+            | SurfaceAST::Not(v) => {
+                vec![v]
+            }
+            SurfaceAST::And(a, b) | SurfaceAST::Or(a, b) => vec![a, b],
+            SurfaceAST::IfThenElse(cond, then, els) => vec![cond, then, els],
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Self> {
+        match self {
+            SurfaceAST::True | SurfaceAST::False | SurfaceAST::Zero | SurfaceAST::Numeral(_) => {
+                vec![]
+            }
+            SurfaceAST::Succ(v)
+            | SurfaceAST::Pred(v)
+            | SurfaceAST::IsZero(v)
+            | SurfaceAST::Not(v) => {
+                vec![v]
+            }
+            SurfaceAST::And(a, b) | SurfaceAST::Or(a, b) => vec![a, b],
+            SurfaceAST::IfThenElse(cond, then, els) => vec![cond, then, els],
+        }
+    }
+
+    fn label(&self) -> String {
+        let label = match self {
+            SurfaceAST::True => "true",
+            SurfaceAST::False => "false",
+            SurfaceAST::Zero => "0",
+            SurfaceAST::Numeral(n) => return n.to_string(),
+            SurfaceAST::Succ(_) => "succ",
+            SurfaceAST::Pred(_) => "pred",
+            SurfaceAST::IsZero(_) => "iszero",
+            SurfaceAST::Not(_) => "not",
+            SurfaceAST::And(..) => "and",
+            SurfaceAST::Or(..) => "or",
+            SurfaceAST::IfThenElse(..) => "if",
+        };
+        label.to_owned()
+    }
+
+    fn from_parts(label: &str, children: Vec<Self>) -> Option<Self> {
+        let arity = children.len();
+        let mut children = children.into_iter().map(Box::new);
+        let mut next = || children.next().unwrap();
+        Some(match (label, arity) {
+            ("true", 0) => SurfaceAST::True,
+            ("false", 0) => SurfaceAST::False,
+            ("0", 0) => SurfaceAST::Zero,
+            ("succ", 1) => SurfaceAST::Succ(next()),
+            ("pred", 1) => SurfaceAST::Pred(next()),
+            ("iszero", 1) => SurfaceAST::IsZero(next()),
+            ("not", 1) => SurfaceAST::Not(next()),
+            ("and", 2) => SurfaceAST::And(next(), next()),
+            ("or", 2) => SurfaceAST::Or(next(), next()),
+            ("if", 3) => SurfaceAST::IfThenElse(next(), next(), next()),
+            (n, 0) => SurfaceAST::Numeral(n.parse().ok()?),
+            _ => return None,
+        })
+    }
+}
+
+impl From<AST> for SurfaceAST {
+    fn from(v: AST) -> Self {
+        let lift = |v: Box<AST>| Box::new(SurfaceAST::from(*v));
+        match v {
+            AST::True => SurfaceAST::True,
+            AST::False => SurfaceAST::False,
+            AST::Zero => SurfaceAST::Zero,
+            AST::Succ(v) => SurfaceAST::Succ(lift(v)),
+            AST::Pred(v) => SurfaceAST::Pred(lift(v)),
+            AST::IsZero(v) => SurfaceAST::IsZero(lift(v)),
+            AST::IfThenElse(cond, then, els) => {
+                SurfaceAST::IfThenElse(lift(cond), lift(then), lift(els))
+            }
+        }
+    }
+}
+
+/// Source spans of a `SurfaceAST`: one node per term node, children in the same order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanTree {
+    pub span: Span,
+    pub children: Vec<SpanTree>,
+}
+
+impl SpanTree {
+    /// The span of the subterm reached by following the child indices in `path`.
+    pub fn get(&self, path: &[usize]) -> Option<Span> {
+        path.iter()
+            .try_fold(self, |tree, &i| tree.children.get(i))
+            .map(|tree| tree.span)
+    }
+}
+
+// Mirrors `SurfaceAST::try_from`, including the left fold of infix operators.
+pub(crate) fn span_tree(pair: Pair<'_, Rule>) -> SpanTree {
+    let span = pair.as_span().into();
+    match pair.as_rule() {
+        Rule::Term | Rule::Conjunction => {
+            let operator = if pair.as_rule() == Rule::Term {
+                "or"
+            } else {
+                "and"
+            };
+            let (src, offset) = (pair.as_str(), pair.as_span().start());
+            let mut operands = pair.into_inner().map(span_tree).peekable();
+            let Some(first) = operands.next() else {
+                return SpanTree {
+                    span,
+                    children: vec![],
+                };
+            };
+            // A bracketed operand's pair leaves out its parentheses, so each node of the
+            // fold runs on to the last `)` before the next operator, or to the end of `pair`.
+            let mut lhs = first;
+            while let Some(rhs) = operands.next() {
+                let end = match operands.peek() {
+                    Some(next) => {
+                        let gap = &src[rhs.span.end - offset..next.span.start - offset];
+                        let closing = gap[..gap.find(operator).unwrap_or(0)].trim_end();
+                        rhs.span.end + closing.len()
+                    }
+                    None => span.end,
+                };
+                lhs = SpanTree {
+                    span: Span::new(span.start, end),
+                    children: vec![lhs, rhs],
+                };
+            }
+            lhs
+        }
+        Rule::Numeral => SpanTree {
+            span,
+            children: vec![],
+        },
+        _ => SpanTree {
+            span,
+            children: pair.into_inner().map(span_tree).collect(),
+        },
+    }
+}
+
+fn if_then_else(cond: AST, then: AST, els: AST) -> AST {
+    AST::IfThenElse(Box::new(cond), Box::new(then), Box::new(els))
+}
+
+// What is left to do for a node of `desugar`: expand it, or, once its children are done,
+// assemble it from them.
+enum Task {
+    Expand(SurfaceAST),
+    Succ,
+    Pred,
+    IsZero,
+    If,
+    Not,
+    And,
+    Or,
+}
+
+/// Expands numerals into `succ` chains and the boolean connectives into `if`,
+/// leaving only constructs the evaluator knows about. Iterative, like the infix fold that
+/// builds the deepest surface terms.
+pub fn desugar(t: SurfaceAST) -> AST {
+    let mut tasks = vec![Task::Expand(t)];
+    let mut done = Vec::new();
+    while let Some(task) = tasks.pop() {
+        let mut pop = || done.pop().unwrap();
+        let v = match task {
+            Task::Expand(t) => {
+                // Children are pushed last first, so they come out done in order.
+                match t {
+                    SurfaceAST::True => done.push(AST::True),
+                    SurfaceAST::False => done.push(AST::False),
+                    SurfaceAST::Zero => done.push(AST::Zero),
+                    SurfaceAST::Numeral(n) => {
+                        done.push((0..n).fold(AST::Zero, |v, _| AST::Succ(Box::new(v))))
+                    }
+                    SurfaceAST::Succ(t) => tasks.extend([Task::Succ, Task::Expand(*t)]),
+                    SurfaceAST::Pred(t) => tasks.extend([Task::Pred, Task::Expand(*t)]),
+                    SurfaceAST::IsZero(t) => tasks.extend([Task::IsZero, Task::Expand(*t)]),
+                    SurfaceAST::Not(t) => tasks.extend([Task::Not, Task::Expand(*t)]),
+                    SurfaceAST::IfThenElse(cond, then, els) => tasks.extend([
+                        Task::If,
+                        Task::Expand(*els),
+                        Task::Expand(*then),
+                        Task::Expand(*cond),
+                    ]),
+                    SurfaceAST::And(a, b) => {
+                        tasks.extend([Task::And, Task::Expand(*b), Task::Expand(*a)])
+                    }
+                    SurfaceAST::Or(a, b) => {
+                        tasks.extend([Task::Or, Task::Expand(*b), Task::Expand(*a)])
+                    }
+                }
+                continue;
+            }
+            Task::Succ => AST::Succ(Box::new(pop())),
+            Task::Pred => AST::Pred(Box::new(pop())),
+            Task::IsZero => AST::IsZero(Box::new(pop())),
+            Task::If => {
+                let (els, then) = (pop(), pop());
+                if_then_else(pop(), then, els)
+            }
+            Task::Not => if_then_else(pop(), AST::False, AST::True),
+            Task::And => {
+                let b = pop();
+                if_then_else(pop(), b, AST::False)
+            }
+            Task::Or => {
+                let b = pop();
+                if_then_else(pop(), AST::True, b)
+            }
+        };
+        done.push(v);
+    }
+    done.pop().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eval_ast, try_parse, try_parse_surface, try_parse_surface_spanned};
+
+    fn core(input: &str) -> AST {
+        desugar(try_parse_surface(input).unwrap())
+    }
+
+    #[test]
+    fn test_desugar_numeral() {
+        assert_eq!(try_parse_surface("3").unwrap(), SurfaceAST::Numeral(3));
+        assert_eq!(core("3"), try_parse("succ succ succ 0").unwrap());
+        assert_eq!(core("0x0"), AST::Zero);
+    }
+
+    #[test]
+    fn test_desugar_connectives() {
+        assert_eq!(
+            core("not true"),
+            try_parse("if true then false else true").unwrap()
+        );
+        assert_eq!(
+            core("iszero 0 and false"),
+            try_parse("if iszero 0 then false else false").unwrap()
+        );
+        assert_eq!(
+            core("false or true"),
+            try_parse("if false then true else true").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_span_tree() {
+        let input = "succ 0 and (iszero true)";
+        let (_, spans) = try_parse_surface_spanned(input).unwrap();
+        let text = |path: &[usize]| {
+            let span = spans.get(path).unwrap();
+            &input[span.start..span.end]
+        };
+        assert_eq!(text(&[]), "succ 0 and (iszero true)");
+        assert_eq!(text(&[0]), "succ 0");
+        assert_eq!(text(&[0, 0]), "0");
+        assert_eq!(text(&[1]), "iszero true");
+        assert_eq!(text(&[1, 0]), "true");
+        assert_eq!(spans.get(&[1, 0, 0]), None);
+
+        let input = "iszero ((0)) or (true) or false";
+        let (_, spans) = try_parse_surface_spanned(input).unwrap();
+        let text = |path: &[usize]| {
+            let span = spans.get(path).unwrap();
+            &input[span.start..span.end]
+        };
+        assert_eq!(text(&[]), input);
+        assert_eq!(text(&[0]), "iszero ((0)) or (true)");
+        assert_eq!(text(&[0, 0]), "iszero ((0))");
+        assert_eq!(text(&[0, 1]), "true");
+    }
+
+    #[test]
+    fn test_connective_precedence() {
+        assert_eq!(
+            try_parse_surface("true or false and false").unwrap(),
+            SurfaceAST::Or(
+                Box::new(SurfaceAST::True),
+                Box::new(SurfaceAST::And(
+                    Box::new(SurfaceAST::False),
+                    Box::new(SurfaceAST::False)
+                ))
+            )
+        );
+        assert_eq!(
+            try_parse_surface("not true and false").unwrap(),
+            SurfaceAST::And(
+                Box::new(SurfaceAST::Not(Box::new(SurfaceAST::True))),
+                Box::new(SurfaceAST::False)
+            )
+        );
+        assert_eq!(eval_ast(core("not (true and false)")).unwrap(), AST::True);
+    }
+}
+
+tapl_syntax::term_like_tests!(
+    term_like,
+    SurfaceAST,
+    [
+        "0x3",
+        "not true",
+        "iszero 2 and false or true",
+        "if true then 1 else pred 0"
+    ]
+    .map(|input| crate::try_parse_surface(input).unwrap())
+    .into()
+);
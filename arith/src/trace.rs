@@ -0,0 +1,75 @@
+use serde_json::{json, Value};
+use tapl_trace::{Snapshot, Step, Trace};
+
+use crate::{
+    eval_ast_traced,
+    output::to_json,
+    pretty::{render_highlights, to_doc_highlighting},
+    EvalOptions, AST,
+};
+
+fn snapshot(v: &AST) -> Snapshot {
+    Snapshot {
+        term: to_json(v),
+        text: v.to_string(),
+    }
+}
+
+/// Evaluates `v`, recording each rewrite in the shared `tapl-trace` format.
+pub fn tapl_trace(v: AST, options: &EvalOptions) -> Trace {
+    let initial = snapshot(&v);
+    let mut before = v.clone();
+    let (steps, result) = eval_ast_traced(v, options);
+    let steps = steps
+        .into_iter()
+        .map(|step| {
+            let doc = to_doc_highlighting(&before, Some(&step.redex));
+            let (_, spans) = render_highlights(&doc, usize::MAX);
+            before = step.term.clone();
+            Step {
+                rule: step.rule.name().to_owned(),
+                redex: step.redex,
+                redex_span: spans.first().map(|span| (span.start, span.end)),
+                after: snapshot(&step.term),
+            }
+        })
+        .collect();
+    Trace {
+        language: "arith".to_owned(),
+        initial,
+        steps,
+        outcome: result.as_ref().map(snapshot).map_err(|e| e.to_string()),
+    }
+}
+
+/// Evaluation steps as a JSON array of `{step, rule, term}` records, numbered from 1,
+/// each with the whole program as it stands after the step.
+pub fn steps_json(steps: &[(&str, AST)]) -> Value {
+    let records = steps
+        .iter()
+        .enumerate()
+        .map(|(i, (rule, term))| json!({ "step": i + 1, "rule": rule, "term": term.to_string() }));
+    Value::Array(records.collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::try_parse;
+
+    #[test]
+    fn test_tapl_trace() {
+        let input = try_parse("if iszero pred succ 0 then succ 0 else false").unwrap();
+        let trace = tapl_trace(input, &EvalOptions::default());
+        assert_eq!(trace.steps.len(), 3);
+        let first = &trace.steps[0];
+        assert_eq!(first.rule, "B-PredSucc");
+        let (start, end) = first.redex_span.unwrap();
+        assert_eq!(&trace.initial.text[start..end], "pred succ 0");
+        assert_eq!(trace.outcome.unwrap().text, "succ 0");
+
+        let stuck = tapl_trace(try_parse("succ iszero 0").unwrap(), &EvalOptions::default());
+        assert_eq!(stuck.steps.len(), 1);
+        assert!(stuck.outcome.is_err());
+    }
+}
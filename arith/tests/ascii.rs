@@ -0,0 +1,33 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+fn run_arith(args: &[&str], input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_arith"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_ascii_only_output() {
+    let input = "if iszero pred 2 then succ 0 else 0\n";
+    let args = ["--output", "tree", "--explain-rule"];
+    assert!(!run_arith(&args, input).is_ascii());
+    let output = run_arith(&[&args[..], &["--ascii-only"]].concat(), input);
+    assert!(output.is_ascii(), "{}", output);
+    assert!(output.contains("+- 0"));
+    assert!(output.contains("B-IfFalse: "));
+}
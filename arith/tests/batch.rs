@@ -0,0 +1,73 @@
+use std::fs;
+
+use arith::{
+    batch::run_batch,
+    cli::{Command, Options},
+    EvalOptions, AST,
+};
+
+#[test]
+fn test_batch_summary() {
+    let dir = std::env::temp_dir().join(format!("arith_batch_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a_numeric.arith"), "pred succ succ 0\n").unwrap();
+    fs::write(dir.join("b_bool.arith"), "iszero 0").unwrap();
+    fs::write(dir.join("c_parse.arith"), "if true then").unwrap();
+    fs::write(dir.join("d_stuck.arith"), "succ false").unwrap();
+    fs::write(dir.join("notes.txt"), "not a program").unwrap();
+
+    let report = run_batch(&dir, &EvalOptions::default()).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(report.results.len(), 4);
+    assert_eq!(report.passed(), 2);
+    assert_eq!(report.errored(), 2);
+    assert_eq!(
+        report.results[0].outcome.as_ref().unwrap(),
+        &AST::Succ(Box::new(AST::Zero))
+    );
+    assert_eq!(report.results[1].outcome.as_ref().unwrap(), &AST::True);
+    assert!(report.results[2].path.ends_with("c_parse.arith"));
+}
+
+#[test]
+fn test_batch_options() {
+    let args = |args: &[&str]| Options::parse(args.iter().map(|arg| arg.to_string()));
+    for order in [
+        ["--strict-numerals", "--batch", "d"],
+        ["--batch", "d", "--strict-numerals"],
+    ] {
+        let options = args(&order).unwrap();
+        assert!(matches!(options.command, Command::Batch(_)));
+        assert!(options.eval.strict_numerals);
+    }
+    assert!(args(&["--expr", "0", "--batch", "d"]).is_none());
+    assert!(args(&["--batch", "d", "--explain-rule"]).is_none());
+    assert!(args(&["--seed", "1"]).is_none());
+    assert!(args(&["gen", "--modulus", "2"]).is_none());
+
+    let dir = std::env::temp_dir().join(format!("arith_batch_options_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("pred.arith"), "pred 0").unwrap();
+    let strict = EvalOptions {
+        strict_numerals: true,
+        ..EvalOptions::default()
+    };
+    let report = run_batch(&dir, &strict).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(report.errored(), 1);
+}
+
+// The same files the language server test checks, so the two agree on the file format.
+#[test]
+fn test_batch_programs() {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../fixtures/programs");
+    let report = run_batch(dir.as_ref(), &EvalOptions::default()).unwrap();
+    assert_eq!(report.results.len(), 2);
+    assert!(report.results[0].path.ends_with("clean.arith"));
+    assert_eq!(
+        report.results[0].outcome.as_ref().unwrap().to_string(),
+        "succ succ 0"
+    );
+    assert!(report.results[1].outcome.is_err());
+}
@@ -0,0 +1,41 @@
+use std::process::Command;
+
+fn run_arith(args: &[&str]) -> (bool, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_arith"))
+        .args(args)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    (output.status.success(), stdout)
+}
+
+#[test]
+fn test_explain_small_step() {
+    let args = ["--small-step", "--explain-rule", "--expr", "pred succ 0"];
+    let (ok, stdout) = run_arith(&args);
+    assert!(ok);
+    let lines: Vec<_> = stdout.lines().skip(2).collect();
+    assert_eq!(lines, ["E-PredSucc: pred succ 0 → 0", "Output: 0"]);
+
+    let (ok, stdout) = run_arith(&["--small-step", "--expr", "iszero pred 1"]);
+    assert!(ok);
+    assert!(stdout.ends_with("Output: true\n"));
+
+    let args = [
+        "--small-step",
+        "--explain-rule",
+        "--modulus",
+        "2",
+        "--expr",
+        "succ 1",
+    ];
+    let (ok, stdout) = run_arith(&args);
+    assert!(ok);
+    let lines: Vec<_> = stdout.lines().skip(2).collect();
+    assert_eq!(lines, ["E-Wrap: succ succ 0 → 0", "Output: 0"]);
+
+    let (ok, _) = run_arith(&["--small-step", "--strict-numerals", "--expr", "pred 0"]);
+    assert!(!ok);
+    let (ok, _) = run_arith(&["--small-step", "--trace-format", "tapl", "--expr", "0"]);
+    assert!(!ok);
+}
@@ -0,0 +1,29 @@
+use std::process::Command;
+
+fn run_gen(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_arith"))
+        .arg("gen")
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_gen_same_seed_same_program() {
+    let args = ["--seed", "2023", "--size", "12"];
+    let first = run_gen(&args);
+    assert_eq!(first, run_gen(&args));
+    let program = arith::try_parse(first.trim()).unwrap();
+    assert!(arith::arith_size(&program) <= 12);
+}
+
+#[test]
+fn test_gen_max_depth() {
+    let args = ["--seed", "7", "--size", "40", "--max-depth", "3"];
+    let first = run_gen(&args);
+    assert_eq!(first, run_gen(&args));
+    let program = arith::try_parse(first.trim()).unwrap();
+    assert!(arith::arith_depth(&program) <= 3);
+}
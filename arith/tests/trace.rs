@@ -0,0 +1,80 @@
+use std::process::Command;
+
+use tapl_trace::{to_html, Trace};
+
+fn traced(expr: &str) -> (bool, Trace) {
+    let output = Command::new(env!("CARGO_BIN_EXE_arith"))
+        .args(["--trace-format", "tapl", "--expr", expr])
+        .output()
+        .unwrap();
+    let json = serde_json::from_slice(&output.stdout).unwrap();
+    (output.status.success(), Trace::from_json(&json).unwrap())
+}
+
+#[test]
+fn test_emits_tapl_trace() {
+    let (ok, trace) = traced("if iszero pred succ 0 then succ 0 else false");
+    assert!(ok);
+    assert_eq!(trace.language, "arith");
+    let rules: Vec<_> = trace.steps.iter().map(|step| step.rule.as_str()).collect();
+    assert_eq!(rules, ["B-PredSucc", "B-IsZeroZero", "B-IfTrue"]);
+    assert_eq!(trace.outcome.as_ref().unwrap().text, "succ 0");
+    assert_eq!(to_html(&trace).matches("class=\"step\"").count(), 3);
+}
+
+#[test]
+fn test_stuck_trace() {
+    let (ok, trace) = traced("if iszero 0 then succ true else 0");
+    assert!(!ok);
+    assert_eq!(trace.steps.len(), 2);
+    assert!(trace.outcome.is_err());
+}
+
+#[test]
+fn test_emit_steps_json() {
+    let steps = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_arith"))
+            .args(["--emit-steps-json", "--expr", "iszero pred succ 0"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+    assert_eq!(
+        steps(&[]),
+        serde_json::json!([
+            { "step": 1, "rule": "B-PredSucc", "term": "iszero 0" },
+            { "step": 2, "rule": "B-IsZeroZero", "term": "true" },
+        ])
+    );
+    assert_eq!(
+        steps(&["--small-step"]),
+        serde_json::json!([
+            { "step": 1, "rule": "E-PredSucc", "term": "iszero 0" },
+            { "step": 2, "rule": "E-IsZeroZero", "term": "true" },
+        ])
+    );
+}
+
+// What `tapl-trace` does with a file: read it the way the viewer does, then render it.
+fn view(expr: &str) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_arith"))
+        .args(["--trace-format", "tapl", "--expr", expr])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let src = String::from_utf8(output.stdout).unwrap();
+    arith::with_stack(|| to_html(&Trace::parse(&src).unwrap()))
+}
+
+#[test]
+fn test_view_large_numeral() {
+    assert_eq!(view("pred 200").matches("class=\"step\"").count(), 1);
+}
+
+#[test]
+fn test_view_deep_term() {
+    let expr = format!("{}0", "pred ".repeat(200));
+    assert_eq!(view(&expr).matches("class=\"step\"").count(), 200);
+}
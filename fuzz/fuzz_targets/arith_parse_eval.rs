@@ -0,0 +1,35 @@
+//! Arbitrary source text through every entry point that accepts it.
+
+#![no_main]
+
+use arith::{
+    eval_ast, eval_stream, pretty::pretty, try_parse, try_parse_recovering,
+    try_parse_surface_spanned, with_stack, ArithError,
+};
+use libfuzzer_sys::fuzz_target;
+use typed_arith::type_of_ast;
+
+fuzz_target!(|src: &str| {
+    // Accepted terms go as deep as `MAX_DEPTH`, too deep for the fuzzer's own stack.
+    with_stack(|| {
+        for result in eval_stream(src) {
+            let _ = result.map_err(|e| e.to_diagnostic());
+        }
+        let _ = try_parse_recovering(src);
+        let _ = try_parse_surface_spanned(src);
+
+        let Ok(term) = try_parse(src) else {
+            return;
+        };
+        // Printing may add parentheses, which can push a term at the nesting limit past it.
+        match try_parse(&pretty(&term, 80)) {
+            Ok(reparsed) => assert_eq!(reparsed, term),
+            Err(e) => assert!(matches!(e, ArithError::TooDeepError), "{}", e),
+        }
+        let value = eval_ast(term.clone());
+        if let Ok(ty) = type_of_ast(&term) {
+            let value = value.expect("well-typed terms do not get stuck");
+            assert_eq!(type_of_ast(&value), Ok(ty));
+        }
+    })
+});
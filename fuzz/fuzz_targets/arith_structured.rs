@@ -0,0 +1,77 @@
+//! Well-formed terms built directly from fuzzer input, so evaluation is exercised on more
+//! than whatever happens to parse.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use arith::{eval_ast, explain::derive, pretty::pretty, try_parse, validate, with_stack, AST};
+use libfuzzer_sys::fuzz_target;
+use typed_arith::type_of_ast;
+
+/// One constructor of a term written in postfix, so that decoding input never recurses,
+/// however deep the term it describes.
+#[derive(Arbitrary, Debug)]
+enum Op {
+    True,
+    False,
+    Zero,
+    Succ,
+    Pred,
+    IsZero,
+    IfThenElse,
+}
+
+// Applies `ops` to a stack of terms, skipping any that lacks operands, and takes the last
+// term built.
+fn build(ops: Vec<Op>) -> Option<AST> {
+    let mut stack: Vec<AST> = Vec::new();
+    for op in ops {
+        let arity = match op {
+            Op::True | Op::False | Op::Zero => 0,
+            Op::Succ | Op::Pred | Op::IsZero => 1,
+            Op::IfThenElse => 3,
+        };
+        if stack.len() < arity {
+            continue;
+        }
+        let mut pop = || Box::new(stack.pop().unwrap());
+        let t = match op {
+            Op::True => AST::True,
+            Op::False => AST::False,
+            Op::Zero => AST::Zero,
+            Op::Succ => AST::Succ(pop()),
+            Op::Pred => AST::Pred(pop()),
+            Op::IsZero => AST::IsZero(pop()),
+            Op::IfThenElse => {
+                let (els, then) = (pop(), pop());
+                AST::IfThenElse(pop(), then, els)
+            }
+        };
+        stack.push(t);
+    }
+    stack.pop()
+}
+
+fuzz_target!(|ops: Vec<Op>| {
+    // Terms go as deep as `MAX_DEPTH`, and everything below recurses on them.
+    with_stack(|| {
+        let Some(term) = build(ops) else {
+            return;
+        };
+        if validate(&term).is_err() {
+            return;
+        }
+        let value = eval_ast(term.clone());
+        match derive(term.clone()) {
+            Ok(derivation) => assert_eq!(Some(&derivation.value), value.as_ref().ok()),
+            Err(_) => assert!(value.is_err()),
+        }
+        if let Ok(reparsed) = try_parse(&pretty(&term, 80)) {
+            assert_eq!(reparsed, term);
+        }
+        if let Ok(ty) = type_of_ast(&term) {
+            let value = value.expect("well-typed terms do not get stuck");
+            assert_eq!(type_of_ast(&value), Ok(ty));
+        }
+    })
+});
@@ -0,0 +1,309 @@
+use std::{fmt::Write, str::FromStr};
+
+use pest::{
+    error::{Error, InputLocation},
+    RuleType,
+};
+
+/// A byte range into a source file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+}
+
+impl From<pest::Span<'_>> for Span {
+    fn from(span: pest::Span<'_>) -> Span {
+        Span::new(span.start(), span.end())
+    }
+}
+
+pub struct SourceFile {
+    pub name: String,
+    pub text: String,
+    line_starts: Vec<usize>,
+}
+
+impl SourceFile {
+    pub fn new(name: impl Into<String>, text: impl Into<String>) -> SourceFile {
+        let text = text.into();
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        SourceFile {
+            name: name.into(),
+            text,
+            line_starts,
+        }
+    }
+
+    /// 1-based line and column of a byte offset.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let column = self.text[self.line_starts[line - 1]..offset.min(self.text.len())]
+            .chars()
+            .count();
+        (line, column + 1)
+    }
+
+    pub fn line(&self, line: usize) -> &str {
+        let start = self.line_starts[line - 1];
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.text.len(), |&next| next - 1);
+        self.text[start..end].trim_end_matches('\r')
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+
+    fn color(self) -> &'static str {
+        match self {
+            Severity::Error => "\x1b[1;31m",
+            Severity::Warning => "\x1b[1;33m",
+            Severity::Note => "\x1b[1;36m",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub labels: Vec<Label>,
+    pub help: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Diagnostic {
+        Diagnostic {
+            severity,
+            message: message.into(),
+            labels: Vec::new(),
+            help: None,
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Error, message)
+    }
+
+    pub fn warning(message: impl Into<String>) -> Diagnostic {
+        Diagnostic::new(Severity::Warning, message)
+    }
+
+    pub fn with_label(mut self, span: Span, message: impl Into<String>) -> Diagnostic {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Diagnostic {
+        self.help = Some(help.into());
+        self
+    }
+
+    /// Builds an error diagnostic from a pest error, labelling the failing position
+    /// with pest's own description of what was expected.
+    pub fn from_pest<R: RuleType>(message: impl Into<String>, err: &Error<R>) -> Diagnostic {
+        let span = match err.location {
+            InputLocation::Pos(pos) => Span::new(pos, pos),
+            InputLocation::Span((start, end)) => Span::new(start, end),
+        };
+        Diagnostic::error(message).with_label(span, err.variant.message())
+    }
+}
+
+const RESET: &str = "\x1b[0m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+
+/// When to colour diagnostics, as chosen by a `--color` flag.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("unknown color choice `{}`", s)),
+        }
+    }
+}
+
+impl ColorChoice {
+    /// `Auto` colours only terminals, and honours `NO_COLOR`.
+    pub fn renderer(self, is_terminal: bool) -> Renderer {
+        let color = match self {
+            ColorChoice::Auto => is_terminal && std::env::var_os("NO_COLOR").is_none(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        };
+        Renderer { color }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Renderer {
+    pub color: bool,
+}
+
+impl Renderer {
+    pub fn plain() -> Renderer {
+        Renderer { color: false }
+    }
+
+    pub fn colored() -> Renderer {
+        Renderer { color: true }
+    }
+
+    fn paint(&self, out: &mut String, style: &str, text: &str) {
+        if self.color {
+            write!(out, "{}{}{}", style, text, RESET).unwrap();
+        } else {
+            out.push_str(text);
+        }
+    }
+
+    /// Renders in the familiar rustc layout: a headline, then every label as
+    /// the offending source line with the span underlined.
+    pub fn render(&self, diagnostic: &Diagnostic, file: &SourceFile) -> String {
+        let mut out = String::new();
+        let severity = diagnostic.severity;
+        self.paint(&mut out, severity.color(), severity.name());
+        self.paint(&mut out, BOLD, &format!(": {}", diagnostic.message));
+        let gutter = diagnostic
+            .labels
+            .iter()
+            .map(|label| file.line_col(label.span.start).0.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let pad = " ".repeat(gutter);
+        for label in &diagnostic.labels {
+            let (line, column) = file.line_col(label.span.start);
+            let text = file.line(line);
+            let (end_line, end_column) = file.line_col(label.span.end);
+            let width = if end_line == line {
+                end_column.saturating_sub(column).max(1)
+            } else {
+                (text.chars().count() + 1).saturating_sub(column).max(1)
+            };
+            out.push('\n');
+            self.paint(&mut out, BLUE, &format!("{}--> ", pad));
+            write!(out, "{}:{}:{}", file.name, line, column).unwrap();
+            out.push('\n');
+            self.paint(&mut out, BLUE, &format!("{} |", pad));
+            out.push('\n');
+            self.paint(&mut out, BLUE, &format!("{:>gutter$} | ", line));
+            out.push_str(text);
+            out.push('\n');
+            self.paint(&mut out, BLUE, &format!("{} | ", pad));
+            out.push_str(&" ".repeat(column - 1));
+            let underline = format!("{} {}", "^".repeat(width), label.message);
+            self.paint(&mut out, severity.color(), underline.trim_end());
+        }
+        if let Some(help) = &diagnostic.help {
+            out.push('\n');
+            self.paint(&mut out, BLUE, &format!("{} = ", pad));
+            write!(out, "help: {}", help).unwrap();
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_col() {
+        let file = SourceFile::new("t", "ab\ncd\n");
+        assert_eq!(file.line_col(0), (1, 1));
+        assert_eq!(file.line_col(2), (1, 3));
+        assert_eq!(file.line_col(3), (2, 1));
+        assert_eq!(file.line_col(6), (3, 1));
+        assert_eq!(file.line(2), "cd");
+        assert_eq!(file.line(3), "");
+    }
+
+    #[test]
+    fn test_render_plain() {
+        let file = SourceFile::new("input.arith", "succ (if true then 0)");
+        let diagnostic = Diagnostic::error("failed to parse input")
+            .with_label(Span::new(20, 20), "expected else")
+            .with_help("every `if` needs an `else` branch");
+        assert_eq!(
+            Renderer::plain().render(&diagnostic, &file),
+            "\
+error: failed to parse input
+ --> input.arith:1:21
+  |
+1 | succ (if true then 0)
+  |                     ^ expected else
+  = help: every `if` needs an `else` branch"
+        );
+    }
+
+    #[test]
+    fn test_render_span_and_no_labels() {
+        let file = SourceFile::new("prog", "0;\niszero true");
+        let diagnostic = Diagnostic::warning("boolean argument").with_label(Span::new(10, 14), "");
+        assert_eq!(
+            Renderer::plain().render(&diagnostic, &file),
+            "\
+warning: boolean argument
+ --> prog:2:8
+  |
+2 | iszero true
+  |        ^^^^"
+        );
+        let diagnostic = Diagnostic::error("stuck");
+        assert_eq!(Renderer::plain().render(&diagnostic, &file), "error: stuck");
+    }
+
+    #[test]
+    fn test_render_colored() {
+        let file = SourceFile::new("t", "x");
+        let diagnostic = Diagnostic::error("bad").with_label(Span::new(0, 1), "here");
+        let rendered = Renderer::colored().render(&diagnostic, &file);
+        assert!(rendered.starts_with("\x1b[1;31merror\x1b[0m"));
+        assert!(rendered.contains("\x1b[1;31m^ here\x1b[0m"));
+    }
+}
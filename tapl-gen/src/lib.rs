@@ -0,0 +1,232 @@
+//! Random term generation shared by the exercise crates. Each language builds its
+//! generator from sized choice, budget splitting and, for binders, `Scope`.
+
+/// xorshift64* generator, so that generated programs only depend on the seed.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // Scrambled by splitmix64, so that nearby seeds give unrelated streams. xorshift
+        // gets stuck on a zero state, and the one seed splitmix64 maps to zero is no
+        // exception, hence the low bit.
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        Rng((z ^ (z >> 31)) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    pub fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+/// A weighted alternative of a sized choice.
+pub type Choice<'a, T> = (u32, &'a dyn Fn(&mut Gen) -> T);
+
+/// A random source paired with the size budget the current subterm may spend.
+pub struct Gen {
+    pub rng: Rng,
+    size: usize,
+}
+
+impl Gen {
+    pub fn new(seed: u64, size: usize) -> Gen {
+        Gen {
+            rng: Rng::new(seed),
+            size,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Runs `f` with the budget temporarily set to `size`.
+    pub fn resize<T>(&mut self, size: usize, f: impl FnOnce(&mut Gen) -> T) -> T {
+        let outer = std::mem::replace(&mut self.size, size);
+        let v = f(self);
+        self.size = outer;
+        v
+    }
+
+    /// Picks an index with probability proportional to its weight.
+    pub fn weighted(&mut self, weights: &[u32]) -> usize {
+        let total: u64 = weights.iter().map(|&w| u64::from(w)).sum();
+        assert!(total > 0, "weighted choice needs a positive weight");
+        let mut pick = self.rng.below(total);
+        for (i, &w) in weights.iter().enumerate() {
+            if pick < u64::from(w) {
+                return i;
+            }
+            pick -= u64::from(w);
+        }
+        unreachable!()
+    }
+
+    /// Chooses among `leaves`, and also among `nodes` while the budget allows a node
+    /// with at least one child.
+    pub fn sized<T>(&mut self, leaves: &[Choice<T>], nodes: &[Choice<T>]) -> T {
+        let choices: Vec<&Choice<T>> = if self.size > 1 {
+            leaves.iter().chain(nodes).collect()
+        } else {
+            leaves.iter().collect()
+        };
+        let weights: Vec<u32> = choices.iter().map(|(w, _)| *w).collect();
+        let (_, f) = choices[self.weighted(&weights)];
+        f(self)
+    }
+
+    /// Splits the budget left after one unit for the node itself into `parts`
+    /// random sizes of at least 1 each. Callers must ensure `size() > parts`.
+    pub fn split(&mut self, parts: usize) -> Vec<usize> {
+        let spare = self.size - 1 - parts;
+        let mut cuts: Vec<usize> = (0..parts.saturating_sub(1))
+            .map(|_| self.rng.below(spare as u64 + 1) as usize)
+            .collect();
+        cuts.push(0);
+        cuts.push(spare);
+        cuts.sort_unstable();
+        cuts.windows(2).map(|w| w[1] - w[0] + 1).collect()
+    }
+
+    /// Generates `parts` children of a node, dividing the budget between them.
+    pub fn children<T>(&mut self, parts: usize, mut f: impl FnMut(&mut Gen) -> T) -> Vec<T> {
+        self.split(parts)
+            .into_iter()
+            .map(|size| self.resize(size, &mut f))
+            .collect()
+    }
+}
+
+/// The binders enclosing a subterm, so generated de Bruijn indices stay closed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Scope {
+    pub depth: usize,
+}
+
+impl Scope {
+    /// The scope under one more binder.
+    pub fn bind(self) -> Scope {
+        Scope {
+            depth: self.depth + 1,
+        }
+    }
+
+    /// A random index bound in this scope; `None` at the top level.
+    pub fn var(self, rng: &mut Rng) -> Option<usize> {
+        (self.depth > 0).then(|| rng.below(self.depth as u64) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng_never_stuck() {
+        for seed in [0, 1, 0x9E37_79B9_7F4A_7C15, u64::MAX] {
+            let mut rng = Rng::new(seed);
+            let draws: Vec<_> = (0..4).map(|_| rng.next_u64()).collect();
+            assert!(draws.iter().any(|&v| v != draws[0]), "seed {:#x}", seed);
+        }
+    }
+
+    // A minimal lambda calculus exercising the binder-aware combinators.
+    enum Lam {
+        Var(usize),
+        Abs(Box<Lam>),
+        App(Box<Lam>, Box<Lam>),
+    }
+
+    // Only called under at least one binder, so a variable is always available.
+    fn open_lam(g: &mut Gen, scope: Scope) -> Lam {
+        let var = |g: &mut Gen| Lam::Var(scope.var(&mut g.rng).unwrap());
+        let abs = |g: &mut Gen| {
+            let size = g.size() - 1;
+            Lam::Abs(Box::new(g.resize(size, |g| open_lam(g, scope.bind()))))
+        };
+        let app = |g: &mut Gen| {
+            if g.size() < 3 {
+                return abs(g);
+            }
+            let mut children = g.children(2, |g| open_lam(g, scope));
+            let arg = children.pop().unwrap();
+            Lam::App(Box::new(children.pop().unwrap()), Box::new(arg))
+        };
+        g.sized(&[(2, &var)], &[(1, &abs), (2, &app)])
+    }
+
+    fn closed_lam(g: &mut Gen) -> Lam {
+        let size = g.size() - 1;
+        Lam::Abs(Box::new(
+            g.resize(size, |g| open_lam(g, Scope::default().bind())),
+        ))
+    }
+
+    fn is_closed(v: &Lam, depth: usize) -> bool {
+        match v {
+            Lam::Var(i) => *i < depth,
+            Lam::Abs(body) => is_closed(body, depth + 1),
+            Lam::App(f, arg) => is_closed(f, depth) && is_closed(arg, depth),
+        }
+    }
+
+    #[test]
+    fn test_weighted_frequencies() {
+        let mut g = Gen::new(7, 0);
+        let picks = (0..10_000).filter(|_| g.weighted(&[1, 3]) == 1).count();
+        assert!((7_200..7_800).contains(&picks), "{}", picks);
+        assert_eq!(g.weighted(&[0, 5, 0]), 1);
+    }
+
+    #[test]
+    fn test_split_budget() {
+        let mut g = Gen::new(11, 20);
+        for parts in 1..10 {
+            let sizes = g.split(parts);
+            assert_eq!(sizes.len(), parts);
+            assert_eq!(sizes.iter().sum::<usize>(), 19);
+            assert!(sizes.iter().all(|&s| s >= 1));
+        }
+        let sizes = g.children(3, |g| g.size());
+        assert_eq!(sizes.iter().sum::<usize>(), 19);
+        assert_eq!(g.size(), 20);
+    }
+
+    #[test]
+    fn test_binder_scoping() {
+        let mut rng = Rng::new(3);
+        assert_eq!(Scope::default().var(&mut rng), None);
+        let scope = Scope::default().bind().bind();
+        assert!((0..100).all(|_| scope.var(&mut rng).is_some_and(|i| i < 2)));
+        let mut g = Gen::new(5, 30);
+        for _ in 0..500 {
+            assert!(is_closed(&closed_lam(&mut g), 0));
+        }
+    }
+
+    #[test]
+    fn test_sized_distribution() {
+        fn size(v: &Lam) -> usize {
+            match v {
+                Lam::Var(_) => 1,
+                Lam::Abs(body) => 1 + size(body),
+                Lam::App(f, arg) => 1 + size(f) + size(arg),
+            }
+        }
+        let mut g = Gen::new(9, 25);
+        let sizes: Vec<usize> = (0..1000).map(|_| size(&closed_lam(&mut g))).collect();
+        assert!(sizes.iter().all(|&s| s <= 25));
+        // Not collapsing to tiny terms, which would make every property vacuous.
+        let mean = sizes.iter().sum::<usize>() as f64 / sizes.len() as f64;
+        assert!(mean > 5.0, "{}", mean);
+        assert!(sizes.iter().any(|&s| s > 15));
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+/// One input file and the blessed output next to it (`foo.txt` and `foo.expected`).
+pub struct Fixture {
+    pub input: PathBuf,
+    pub expected: PathBuf,
+}
+
+/// Finds every `*.<extension>` file directly inside `dir`, in sorted order.
+pub fn discover(dir: &Path, extension: &str) -> std::io::Result<Vec<Fixture>> {
+    let mut inputs = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == extension) {
+            inputs.push(path);
+        }
+    }
+    inputs.sort();
+    Ok(inputs
+        .into_iter()
+        .map(|input| Fixture {
+            expected: input.with_extension("expected"),
+            input,
+        })
+        .collect())
+}
+
+/// Makes output comparable across platforms: LF line endings, no trailing
+/// whitespace and exactly one final newline.
+pub fn normalize(output: &str) -> String {
+    let mut lines: Vec<&str> = output.lines().map(str::trim_end).collect();
+    while lines.last() == Some(&"") {
+        lines.pop();
+    }
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Op {
+    Keep,
+    Delete,
+    Insert,
+}
+
+fn diff_ops<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Op, &'a str)> {
+    // lcs[i][j] is the length of the longest common subsequence of old[i..] and new[j..].
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut ops = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            ops.push((Op::Keep, old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push((Op::Delete, old[i]));
+            i += 1;
+        } else {
+            ops.push((Op::Insert, new[j]));
+            j += 1;
+        }
+    }
+    ops
+}
+
+const CONTEXT: usize = 3;
+
+/// A unified diff from `expected` to `actual`, empty when they are equal.
+pub fn unified_diff(expected: &str, actual: &str) -> String {
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+    let ops = diff_ops(&old, &new);
+    // Merge the context windows around each change into hunks of op indices.
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (k, _) in ops
+        .iter()
+        .enumerate()
+        .filter(|(_, (op, _))| *op != Op::Keep)
+    {
+        let start = k.saturating_sub(CONTEXT);
+        let end = (k + CONTEXT + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    if hunks.is_empty() {
+        return String::new();
+    }
+    let mut out = String::from("--- expected\n+++ actual\n");
+    for (start, end) in hunks {
+        let count = |ops: &[(Op, &str)], skip: Op| ops.iter().filter(|(op, _)| *op != skip).count();
+        let old_start = count(&ops[..start], Op::Insert) + 1;
+        let new_start = count(&ops[..start], Op::Delete) + 1;
+        let hunk = &ops[start..end];
+        let _ = writeln!(
+            out,
+            "@@ -{},{} +{},{} @@",
+            old_start,
+            count(hunk, Op::Insert),
+            new_start,
+            count(hunk, Op::Delete)
+        );
+        for (op, line) in hunk {
+            let sign = match op {
+                Op::Keep => ' ',
+                Op::Delete => '-',
+                Op::Insert => '+',
+            };
+            let _ = writeln!(out, "{}{}", sign, line);
+        }
+    }
+    out
+}
+
+/// Runs `run` on every `*.<extension>` fixture in `dir` and compares its normalized
+/// output with the `.expected` file, panicking with a diff per mismatch. With
+/// `BLESS=1` set, the expected files are rewritten instead.
+pub fn check(dir: impl AsRef<Path>, extension: &str, run: impl Fn(&str) -> String) {
+    let dir = dir.as_ref();
+    let bless = std::env::var_os("BLESS").is_some_and(|v| v == "1");
+    let fixtures = discover(dir, extension)
+        .unwrap_or_else(|e| panic!("cannot read fixtures in {}: {}", dir.display(), e));
+    assert!(
+        !fixtures.is_empty(),
+        "no *.{} fixtures in {}",
+        extension,
+        dir.display()
+    );
+    let mut failures = Vec::new();
+    for fixture in &fixtures {
+        let input = std::fs::read_to_string(&fixture.input)
+            .unwrap_or_else(|e| panic!("cannot read {}: {}", fixture.input.display(), e));
+        let actual = normalize(&run(input.trim_end()));
+        if bless {
+            std::fs::write(&fixture.expected, &actual)
+                .unwrap_or_else(|e| panic!("cannot write {}: {}", fixture.expected.display(), e));
+            continue;
+        }
+        match std::fs::read_to_string(&fixture.expected) {
+            Ok(expected) => {
+                let diff = unified_diff(&normalize(&expected), &actual);
+                if !diff.is_empty() {
+                    failures.push(format!("{}:\n{}", fixture.input.display(), diff));
+                }
+            }
+            Err(_) => failures.push(format!(
+                "{}: missing {}, run with BLESS=1 to create it",
+                fixture.input.display(),
+                fixture.expected.display()
+            )),
+        }
+    }
+    assert!(
+        failures.is_empty(),
+        "{} of {} golden fixtures failed:\n\n{}",
+        failures.len(),
+        fixtures.len(),
+        failures.join("\n")
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("a  \r\nb\n\n\n"), "a\nb\n");
+        assert_eq!(normalize("a"), "a\n");
+        assert_eq!(normalize(""), "");
+    }
+
+    #[test]
+    fn test_unified_diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n"), "");
+        assert_eq!(
+            unified_diff("1\n2\n3\n4\n5\n6\n7\n8\n", "1\n2\n3\n4\nfive\n6\n7\n8\n"),
+            "\
+--- expected
++++ actual
+@@ -2,7 +2,7 @@
+ 2
+ 3
+ 4
+-5
++five
+ 6
+ 7
+ 8
+"
+        );
+    }
+
+    #[test]
+    fn test_unified_diff_separate_hunks() {
+        let old = "a\n1\n2\n3\n4\n5\n6\n7\n8\nb\n";
+        let new = "A\n1\n2\n3\n4\n5\n6\n7\n8\n";
+        assert_eq!(
+            unified_diff(old, new),
+            "\
+--- expected
++++ actual
+@@ -1,4 +1,4 @@
+-a
++A
+ 1
+ 2
+ 3
+@@ -7,4 +7,3 @@
+ 6
+ 7
+ 8
+-b
+"
+        );
+    }
+}
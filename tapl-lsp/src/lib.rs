@@ -0,0 +1,290 @@
+//! A language server for exercise files, spoken over stdio.
+//!
+//! `.arith` files get parse and type errors as diagnostics, and an "evaluate selection"
+//! code action that shows the selection's value. Documents are synchronised in full on
+//! every change, which is plenty for files this size.
+
+pub mod rpc;
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use arith::{eval, statements, try_parse_surface_spanned};
+use serde_json::{json, Value};
+use tapl_diagnostics::{Diagnostic, Severity, Span};
+use typed_arith::type_of;
+
+pub const EVALUATE: &str = "tapl.evaluate";
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Parse and type errors in each statement of an `.arith` file. Errors without a
+/// position of their own are attached to the whole statement.
+pub fn check_arith(src: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for (span, statement) in statements(src) {
+        let diagnostic = match try_parse_surface_spanned(&statement) {
+            Ok((term, spans)) => match type_of(&term) {
+                Ok(_) => continue,
+                Err(e) => e.to_diagnostic(&spans),
+            },
+            Err(e) => e.to_diagnostic(),
+        };
+        if diagnostic.labels.is_empty() {
+            diagnostics.push(diagnostic.with_label(span, ""));
+        } else {
+            diagnostics.push(diagnostic);
+        }
+    }
+    diagnostics
+}
+
+/// An LSP position: zero-based line, and column in UTF-16 code units.
+fn position(text: &str, offset: usize) -> Value {
+    let before = &text[..offset.min(text.len())];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    json!({
+        "line": before.matches('\n').count(),
+        "character": before[line_start..].encode_utf16().count(),
+    })
+}
+
+fn offset(text: &str, position: &Value) -> Option<usize> {
+    let line = position.get("line")?.as_u64()? as usize;
+    let character = position.get("character")?.as_u64()? as usize;
+    let line_start = match line {
+        0 => 0,
+        _ => text.match_indices('\n').nth(line - 1)?.0 + 1,
+    };
+    let mut units = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if units >= character || c == '\n' {
+            return Some(line_start + i);
+        }
+        units += c.len_utf16();
+    }
+    Some(text.len())
+}
+
+fn range(text: &str, span: Span) -> Value {
+    json!({ "start": position(text, span.start), "end": position(text, span.end) })
+}
+
+fn to_lsp(text: &str, diagnostic: &Diagnostic) -> Value {
+    let severity = match diagnostic.severity {
+        Severity::Error => 1,
+        Severity::Warning => 2,
+        Severity::Note => 3,
+    };
+    let message = std::iter::once(diagnostic.message.as_str())
+        .chain(diagnostic.labels.iter().map(|l| l.message.as_str()))
+        .chain(diagnostic.help.as_deref())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    json!({
+        "range": range(text, diagnostic.labels[0].span),
+        "severity": severity,
+        "source": "tapl",
+        "message": message,
+    })
+}
+
+fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+fn response(id: &Value, result: Result<Value, (i64, String)>) -> Value {
+    match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": code, "message": message },
+        }),
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Server {
+    documents: HashMap<String, String>,
+}
+
+impl Server {
+    pub fn new() -> Server {
+        Server::default()
+    }
+
+    /// The messages to send in reply to one from the client, or `None` once the client
+    /// has asked the server to exit.
+    pub fn handle(&mut self, message: &Value) -> Option<Vec<Value>> {
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = message.get("params").unwrap_or(&Value::Null);
+        let mut replies = Vec::new();
+        let result = match method {
+            "exit" => return None,
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "codeActionProvider": true,
+                    "executeCommandProvider": { "commands": [EVALUATE] },
+                },
+                "serverInfo": { "name": "tapl-lsp" },
+            })),
+            "shutdown" => Ok(Value::Null),
+            "textDocument/didOpen" | "textDocument/didChange" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                let text = params["textDocument"]["text"]
+                    .as_str()
+                    .or_else(|| params["contentChanges"].as_array()?.last()?["text"].as_str());
+                if let Some(text) = text {
+                    self.documents.insert(uri.to_owned(), text.to_owned());
+                    replies.extend(self.publish(uri));
+                }
+                Ok(Value::Null)
+            }
+            "textDocument/didClose" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+                self.documents.remove(uri);
+                Ok(Value::Null)
+            }
+            "textDocument/codeAction" => Ok(self.code_actions(params)),
+            "workspace/executeCommand" => match params["arguments"][0].as_str() {
+                Some(src) if params["command"] == EVALUATE => {
+                    let (kind, text) = match eval(src) {
+                        Ok(v) => (3, format!("{} ⇓ {}", src.trim(), v)),
+                        Err(e) => (1, e.to_string()),
+                    };
+                    replies.push(notification(
+                        "window/showMessage",
+                        json!({ "type": kind, "message": text }),
+                    ));
+                    Ok(Value::Null)
+                }
+                _ => Err((INVALID_PARAMS, "unknown command".to_owned())),
+            },
+            _ => Err((METHOD_NOT_FOUND, format!("unsupported method `{}`", method))),
+        };
+        // Notifications carry no id and never get a response, not even an error.
+        if let Some(id) = message.get("id") {
+            replies.push(response(id, result));
+        }
+        Some(replies)
+    }
+
+    fn publish(&self, uri: &str) -> Option<Value> {
+        if !uri.ends_with(".arith") {
+            return None;
+        }
+        let text = &self.documents[uri];
+        let diagnostics: Vec<Value> = check_arith(text).iter().map(|d| to_lsp(text, d)).collect();
+        Some(notification(
+            "textDocument/publishDiagnostics",
+            json!({ "uri": uri, "diagnostics": diagnostics }),
+        ))
+    }
+
+    fn code_actions(&self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+        let selection = self
+            .documents
+            .get(uri)
+            .filter(|_| uri.ends_with(".arith"))
+            .and_then(|text| {
+                let start = offset(text, &params["range"]["start"])?;
+                let end = offset(text, &params["range"]["end"])?;
+                text.get(start..end).filter(|s| !s.trim().is_empty())
+            });
+        match selection {
+            Some(src) => json!([{
+                "title": "Evaluate selection",
+                "command": { "title": "Evaluate selection", "command": EVALUATE, "arguments": [src] },
+            }]),
+            None => json!([]),
+        }
+    }
+}
+
+/// Serves one client until it sends `exit` or closes the input.
+pub fn serve(mut input: impl BufRead, mut output: impl Write) -> io::Result<()> {
+    let mut server = Server::new();
+    while let Some(body) = rpc::read_body(&mut input)? {
+        // The id of a message that is not JSON is unknown, so the error goes to a null id.
+        let message = match serde_json::from_slice(&body) {
+            Ok(message) => message,
+            Err(e) => {
+                let reply = response(&Value::Null, Err((PARSE_ERROR, e.to_string())));
+                rpc::write_message(&mut output, &reply)?;
+                continue;
+            }
+        };
+        let Some(replies) = server.handle(&message) else {
+            break;
+        };
+        for reply in &replies {
+            rpc::write_message(&mut output, reply)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positions() {
+        let text = "é𝕏;\nsucc true";
+        assert_eq!(position(text, 6), json!({"line": 0, "character": 3}));
+        assert_eq!(position(text, 8), json!({"line": 1, "character": 0}));
+        assert_eq!(offset(text, &json!({"line": 0, "character": 3})), Some(6));
+        assert_eq!(offset(text, &json!({"line": 0, "character": 99})), Some(7));
+        assert_eq!(offset(text, &json!({"line": 1, "character": 4})), Some(12));
+        assert_eq!(offset(text, &json!({"line": 5, "character": 0})), None);
+    }
+
+    #[test]
+    fn test_serve_malformed_body() {
+        let mut input = Vec::new();
+        input.extend_from_slice(b"Content-Length: 5\r\n\r\n{oops");
+        let shutdown = json!({"jsonrpc": "2.0", "id": 1, "method": "shutdown"});
+        rpc::write_message(&mut input, &shutdown).unwrap();
+        let mut output = Vec::new();
+        serve(input.as_slice(), &mut output).unwrap();
+        let mut output = output.as_slice();
+        let error = rpc::read_message(&mut output).unwrap().unwrap();
+        assert_eq!(error["id"], Value::Null);
+        assert_eq!(error["error"]["code"], PARSE_ERROR);
+        let reply = rpc::read_message(&mut output).unwrap().unwrap();
+        assert_eq!(reply, json!({"jsonrpc": "2.0", "id": 1, "result": null}));
+    }
+
+    #[test]
+    fn test_check_arith() {
+        let src = "succ 0;\nsucc true;\npred (;\n0x5000";
+        let diagnostics = check_arith(src);
+        let spans: Vec<_> = diagnostics
+            .iter()
+            .map(|d| &src[d.labels[0].span.start..d.labels[0].span.end])
+            .collect();
+        assert_eq!(spans, ["true", "", "0x5000"]);
+        assert_eq!(diagnostics[0].message, "succ expects Nat, found Bool");
+    }
+
+    // The same files `arith --batch` is tested on, so the two agree on the file format.
+    #[test]
+    fn test_check_programs() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/../fixtures/programs");
+        let read = |name| std::fs::read_to_string(format!("{}/{}", dir, name)).unwrap();
+        assert!(check_arith(&read("clean.arith")).is_empty());
+        let src = read("stuck.arith");
+        let diagnostics = check_arith(&src);
+        assert_eq!(diagnostics.len(), 1);
+        let span = &diagnostics[0].labels[0].span;
+        assert_eq!(&src[span.start..span.end], "true");
+    }
+}
@@ -0,0 +1,12 @@
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    // Requests carry whole documents, which may nest as deep as the parser allows.
+    match arith::with_stack(|| tapl_lsp::serve(std::io::stdin().lock(), std::io::stdout().lock())) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
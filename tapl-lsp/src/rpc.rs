@@ -0,0 +1,98 @@
+//! The base protocol: JSON-RPC messages behind `Content-Length` headers.
+
+use std::io::{self, BufRead, Write};
+
+use serde_json::Value;
+
+/// The largest body accepted, well above any document worth checking; a longer
+/// `Content-Length` is rejected before anything is allocated for it.
+pub const MAX_CONTENT_LENGTH: usize = 64 << 20;
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Reads the body of the next message, or `None` if the input ended cleanly between
+/// messages. The body is not checked to be JSON.
+pub fn read_body(input: &mut impl BufRead) -> io::Result<Option<Vec<u8>>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return match length {
+                None => Ok(None),
+                Some(_) => Err(invalid("input ended inside a message header")),
+            };
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                let value = value.trim().parse().map_err(|_| invalid(line))?;
+                length = Some(value);
+            }
+        }
+    }
+    let length = length.ok_or_else(|| invalid("message has no Content-Length header"))?;
+    if length > MAX_CONTENT_LENGTH {
+        return Err(invalid(format!(
+            "Content-Length {} exceeds the maximum of {}",
+            length, MAX_CONTENT_LENGTH
+        )));
+    }
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(body))
+}
+
+/// Reads the next message, or `None` if the input ended cleanly between messages.
+pub fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    match read_body(input)? {
+        Some(body) => serde_json::from_slice(&body)
+            .map(Some)
+            .map_err(io::Error::from),
+        None => Ok(None),
+    }
+}
+
+pub fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let messages = [
+            json!({"jsonrpc": "2.0", "method": "exit"}),
+            json!({"id": 1, "result": "é"}),
+        ];
+        let mut buffer = Vec::new();
+        for message in &messages {
+            write_message(&mut buffer, message).unwrap();
+        }
+        let mut input = buffer.as_slice();
+        for message in &messages {
+            assert_eq!(read_message(&mut input).unwrap().as_ref(), Some(message));
+        }
+        assert!(read_message(&mut input).unwrap().is_none());
+        assert!(read_message(&mut "Content-Type: x\r\n\r\n{}".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_length_cap() {
+        let header = format!("Content-Length: {}\r\n\r\n", u64::MAX);
+        let err = read_body(&mut header.as_bytes()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let header = format!("Content-Length: {}\r\n\r\n", MAX_CONTENT_LENGTH + 1);
+        assert!(read_body(&mut header.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,111 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use serde_json::{json, Value};
+use tapl_lsp::rpc::{read_message, write_message};
+
+/// Sends `messages` to a fresh server, then returns everything it wrote before exiting.
+fn session(messages: &[Value]) -> Vec<Value> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_tapl-lsp"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = child.stdin.take().unwrap();
+    for message in messages {
+        write_message(&mut stdin, message).unwrap();
+    }
+    write_message(&mut stdin, &json!({"jsonrpc": "2.0", "method": "exit"})).unwrap();
+    stdin.flush().unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    let mut stdout = output.stdout.as_slice();
+    std::iter::from_fn(|| read_message(&mut stdout).unwrap()).collect()
+}
+
+fn open(uri: &str, text: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "method": "textDocument/didOpen",
+        "params": {"textDocument": {"uri": uri, "languageId": "arith", "version": 1, "text": text}},
+    })
+}
+
+#[test]
+fn test_initialize_and_shutdown() {
+    let replies = session(&[
+        json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {}}),
+        json!({"jsonrpc": "2.0", "method": "initialized", "params": {}}),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "textDocument/hover", "params": {}}),
+        json!({"jsonrpc": "2.0", "id": 3, "method": "shutdown"}),
+    ]);
+    assert_eq!(replies.len(), 3);
+    assert_eq!(replies[0]["id"], 1);
+    assert_eq!(replies[0]["result"]["capabilities"]["textDocumentSync"], 1);
+    assert_eq!(replies[1]["error"]["code"], -32601);
+    assert_eq!(
+        replies[2],
+        json!({"jsonrpc": "2.0", "id": 3, "result": null})
+    );
+}
+
+#[test]
+fn test_diagnostics() {
+    let replies = session(&[
+        open("file:///a.arith", "iszero 0;\nif true then 0 else false"),
+        json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/didChange",
+            "params": {
+                "textDocument": {"uri": "file:///a.arith", "version": 2},
+                "contentChanges": [{"text": "iszero 0"}],
+            },
+        }),
+        open("file:///notes.txt", "succ true"),
+    ]);
+    assert_eq!(replies.len(), 2);
+    assert_eq!(replies[0]["method"], "textDocument/publishDiagnostics");
+    assert_eq!(
+        replies[0]["params"]["diagnostics"],
+        json!([{
+            "range": {"start": {"line": 1, "character": 20}, "end": {"line": 1, "character": 25}},
+            "severity": 1,
+            "source": "tapl",
+            "message": "if branches have different types: Nat and Bool\nthis has type Bool",
+        }])
+    );
+    assert_eq!(replies[1]["params"]["diagnostics"], json!([]));
+}
+
+#[test]
+fn test_evaluate_selection() {
+    let replies = session(&[
+        open("file:///a.arith", "0;\nif iszero 0 then succ 2 else 0"),
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "textDocument/codeAction",
+            "params": {
+                "textDocument": {"uri": "file:///a.arith"},
+                "range": {"start": {"line": 1, "character": 0}, "end": {"line": 1, "character": 31}},
+                "context": {"diagnostics": []},
+            },
+        }),
+    ]);
+    let command = &replies[1]["result"][0]["command"];
+    assert_eq!(command["command"], "tapl.evaluate");
+    let replies = session(&[json!({
+        "jsonrpc": "2.0",
+        "id": 2,
+        "method": "workspace/executeCommand",
+        "params": {"command": command["command"], "arguments": command["arguments"]},
+    })]);
+    assert_eq!(replies[0]["method"], "window/showMessage");
+    assert_eq!(
+        replies[0]["params"]["message"],
+        "if iszero 0 then succ 2 else 0 ⇓ succ succ succ 0"
+    );
+    assert_eq!(replies[1]["result"], Value::Null);
+}
@@ -0,0 +1,293 @@
+//! A Wadler-style document algebra shared by the exercise printers: each language
+//! builds a `Doc` for its terms and `render_to` lays it out for a target.
+
+use std::ops::Range;
+
+pub enum Doc {
+    Nil,
+    Text(String),
+    Line,
+    Concat(Box<Doc>, Box<Doc>),
+    Nest(usize, Box<Doc>),
+    Group(Box<Doc>),
+    /// Emphasized by the target, e.g. the subterm a trace step rewrites.
+    Highlight(Box<Doc>),
+}
+
+impl Doc {
+    pub fn text(s: impl Into<String>) -> Doc {
+        Doc::Text(s.into())
+    }
+
+    pub fn concat(self, other: Doc) -> Doc {
+        Doc::Concat(Box::new(self), Box::new(other))
+    }
+
+    pub fn nest(self, indent: usize) -> Doc {
+        Doc::Nest(indent, Box::new(self))
+    }
+
+    pub fn group(self) -> Doc {
+        Doc::Group(Box::new(self))
+    }
+
+    pub fn highlight(self) -> Doc {
+        Doc::Highlight(Box::new(self))
+    }
+
+    pub fn parens(self) -> Doc {
+        Doc::text("(").concat(self).concat(Doc::text(")"))
+    }
+}
+
+/// Parenthesizes `doc`, whose operator binds with precedence `inner`, when it
+/// appears where at least precedence `outer` is required.
+pub fn with_prec(outer: u8, inner: u8, doc: Doc) -> Doc {
+    if inner < outer {
+        doc.parens()
+    } else {
+        doc
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Target {
+    #[default]
+    Plain,
+    /// Highlights in bold underline.
+    Ansi,
+    /// For an `alltt` environment: braces and backslashes escaped, highlights in bold.
+    Latex,
+}
+
+impl Target {
+    fn highlight_start(self) -> &'static str {
+        match self {
+            Target::Plain => "",
+            Target::Ansi => "\x1b[1;4m",
+            Target::Latex => "\\textbf{",
+        }
+    }
+
+    fn highlight_end(self) -> &'static str {
+        match self {
+            Target::Plain => "",
+            Target::Ansi => "\x1b[0m",
+            Target::Latex => "}",
+        }
+    }
+
+    fn push_text(self, out: &mut String, s: &str) {
+        match self {
+            Target::Latex => {
+                for c in s.chars() {
+                    match c {
+                        '\\' => out.push_str("\\textbackslash{}"),
+                        '{' | '}' => {
+                            out.push('\\');
+                            out.push(c);
+                        }
+                        c => out.push(c),
+                    }
+                }
+            }
+            _ => out.push_str(s),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Mode {
+    Flat,
+    Break,
+}
+
+#[derive(Clone, Copy)]
+enum Cmd<'d> {
+    Doc(usize, Mode, &'d Doc),
+    EndHighlight,
+}
+
+// Whether `cmd` laid out flat, followed by `rest` up to its next line break, fits in `width`.
+fn fits(mut width: usize, cmd: Cmd<'_>, rest: &[Cmd<'_>]) -> bool {
+    let mut stack = vec![cmd];
+    let mut rest = rest.iter().rev();
+    loop {
+        let cmd = match stack.pop() {
+            Some(cmd) => cmd,
+            None => match rest.next() {
+                Some(&cmd) => cmd,
+                None => return true,
+            },
+        };
+        let Cmd::Doc(indent, mode, doc) = cmd else {
+            continue;
+        };
+        match doc {
+            Doc::Nil => {}
+            Doc::Text(s) => match width.checked_sub(s.len()) {
+                Some(w) => width = w,
+                None => return false,
+            },
+            Doc::Line => match mode {
+                Mode::Flat => match width.checked_sub(1) {
+                    Some(w) => width = w,
+                    None => return false,
+                },
+                Mode::Break => return true,
+            },
+            Doc::Concat(a, b) => {
+                stack.push(Cmd::Doc(indent, mode, b));
+                stack.push(Cmd::Doc(indent, mode, a));
+            }
+            Doc::Nest(i, d) => stack.push(Cmd::Doc(indent + i, mode, d)),
+            Doc::Group(d) | Doc::Highlight(d) => stack.push(Cmd::Doc(indent, mode, d)),
+        }
+    }
+}
+
+fn layout(doc: &Doc, width: usize, target: Target) -> (String, Vec<Range<usize>>) {
+    let mut out = String::new();
+    let mut highlights = Vec::new();
+    let mut open = Vec::new();
+    let mut column = 0;
+    let mut stack: Vec<Cmd<'_>> = vec![Cmd::Doc(0, Mode::Break, doc)];
+    while let Some(cmd) = stack.pop() {
+        let (indent, mode, doc) = match cmd {
+            Cmd::Doc(indent, mode, doc) => (indent, mode, doc),
+            Cmd::EndHighlight => {
+                highlights.push(open.pop().unwrap()..out.len());
+                out.push_str(target.highlight_end());
+                continue;
+            }
+        };
+        match doc {
+            Doc::Nil => {}
+            Doc::Text(s) => {
+                target.push_text(&mut out, s);
+                column += s.len();
+            }
+            Doc::Line => match mode {
+                Mode::Flat => {
+                    out.push(' ');
+                    column += 1;
+                }
+                Mode::Break => {
+                    out.push('\n');
+                    out.push_str(&" ".repeat(indent));
+                    column = indent;
+                }
+            },
+            Doc::Concat(a, b) => {
+                stack.push(Cmd::Doc(indent, mode, b));
+                stack.push(Cmd::Doc(indent, mode, a));
+            }
+            Doc::Nest(i, d) => stack.push(Cmd::Doc(indent + i, mode, d)),
+            Doc::Group(d) => {
+                let remaining = width.saturating_sub(column);
+                let mode = match mode {
+                    Mode::Flat => Mode::Flat,
+                    Mode::Break if fits(remaining, Cmd::Doc(indent, Mode::Flat, d), &stack) => {
+                        Mode::Flat
+                    }
+                    Mode::Break => Mode::Break,
+                };
+                stack.push(Cmd::Doc(indent, mode, d));
+            }
+            Doc::Highlight(d) => {
+                out.push_str(target.highlight_start());
+                open.push(out.len());
+                stack.push(Cmd::EndHighlight);
+                stack.push(Cmd::Doc(indent, mode, d));
+            }
+        }
+    }
+    (out, highlights)
+}
+
+/// Lays `doc` out in `width` columns; highlight markup takes up no columns.
+pub fn render_to(doc: &Doc, width: usize, target: Target) -> String {
+    layout(doc, width, target).0
+}
+
+pub fn render(doc: &Doc, width: usize) -> String {
+    render_to(doc, width, Target::Plain)
+}
+
+/// Renders `doc` as plain text, also returning the byte range of every highlight
+/// in the order they close, for front ends that do their own emphasis.
+pub fn render_highlights(doc: &Doc, width: usize) -> (String, Vec<Range<usize>>) {
+    layout(doc, width, Target::Plain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `f(a, b, ...)` with the arguments on their own lines when it does not fit.
+    fn call(name: &str, args: Vec<Doc>) -> Doc {
+        let mut body = Doc::Nil;
+        for (i, arg) in args.into_iter().enumerate() {
+            let sep = if i == 0 { Doc::Nil } else { Doc::text(",") };
+            body = body.concat(sep).concat(Doc::Line).concat(arg);
+        }
+        Doc::text(name)
+            .concat(Doc::text("("))
+            .concat(body.nest(2))
+            .concat(Doc::text(")"))
+            .group()
+    }
+
+    fn sample() -> Doc {
+        call(
+            "f",
+            vec![
+                Doc::text("alpha"),
+                call("g", vec![Doc::text("beta"), Doc::text("gamma")]),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_render_widths() {
+        assert_eq!(render(&sample(), 80), "f( alpha, g( beta, gamma))");
+        assert_eq!(render(&sample(), 26), "f( alpha, g( beta, gamma))");
+        assert_eq!(render(&sample(), 25), "f(\n  alpha,\n  g( beta, gamma))");
+        assert_eq!(
+            render(&sample(), 10),
+            "f(\n  alpha,\n  g(\n    beta,\n    gamma))"
+        );
+        assert_eq!(render(&Doc::Nil, 0), "");
+    }
+
+    #[test]
+    fn test_with_prec() {
+        assert_eq!(render(&with_prec(2, 1, Doc::text("a b")), 80), "(a b)");
+        assert_eq!(render(&with_prec(1, 1, Doc::text("a b")), 80), "a b");
+    }
+
+    #[test]
+    fn test_highlight_targets() {
+        let doc = Doc::text("succ ")
+            .concat(Doc::text("{0}").highlight())
+            .concat(Doc::Line)
+            .concat(Doc::text("x"))
+            .group();
+        assert_eq!(render_to(&doc, 80, Target::Plain), "succ {0} x");
+        assert_eq!(
+            render_to(&doc, 80, Target::Ansi),
+            "succ \x1b[1;4m{0}\x1b[0m x"
+        );
+        assert_eq!(
+            render_to(&doc, 80, Target::Latex),
+            "succ \\textbf{\\{0\\}} x"
+        );
+        let (text, spans) = render_highlights(&doc, 80);
+        assert_eq!(text, "succ {0} x");
+        assert_eq!(spans.len(), 1);
+        assert_eq!(spans[0], 5..8);
+        // Markup does not count towards the width.
+        assert_eq!(render_to(&doc, 10, Target::Ansi).lines().count(), 1);
+        assert_eq!(render_to(&doc, 9, Target::Ansi).lines().count(), 2);
+    }
+}
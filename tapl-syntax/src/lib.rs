@@ -0,0 +1,267 @@
+//! Term-shape utilities written once for every language's syntax tree.
+//!
+//! A language implements `TermLike` for its term types; metrics, DOT export and
+//! path-based navigation then come for free and behave identically everywhere.
+//! Every walk is iterative, so no term is too deep to measure or draw.
+
+use std::{collections::BTreeMap, fmt::Write};
+
+pub trait TermLike: Sized {
+    /// Immediate subterms, in the order child paths index them.
+    fn children(&self) -> Vec<&Self>;
+
+    /// Immediate subterms, mutably, in the same order as `children`.
+    fn children_mut(&mut self) -> Vec<&mut Self>;
+
+    /// The constructor, as the language itself writes it.
+    fn label(&self) -> String;
+
+    /// Builds a node with `label` and exactly these children, or `None` if the language
+    /// has no such constructor at that arity. Generators and tests use this to make terms
+    /// without knowing the concrete type; anything else a node carries, such as a source
+    /// span, is up to the implementation.
+    fn from_parts(label: &str, children: Vec<Self>) -> Option<Self>;
+}
+
+/// The number of nodes in `t`.
+pub fn size<T: TermLike>(t: &T) -> usize {
+    let mut stack = vec![t];
+    let mut size = 0;
+    while let Some(t) = stack.pop() {
+        size += 1;
+        stack.extend(t.children());
+    }
+    size
+}
+
+/// The number of nodes on the longest path from `t` to a leaf.
+pub fn depth<T: TermLike>(t: &T) -> usize {
+    let mut stack = vec![(t, 1)];
+    let mut max = 0;
+    while let Some((t, depth)) = stack.pop() {
+        max = max.max(depth);
+        stack.extend(t.children().into_iter().map(|child| (child, depth + 1)));
+    }
+    max
+}
+
+/// How many nodes of `t` carry each label.
+pub fn histogram<T: TermLike>(t: &T) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    let mut stack = vec![t];
+    while let Some(t) = stack.pop() {
+        *counts.entry(t.label()).or_insert(0) += 1;
+        stack.extend(t.children());
+    }
+    counts
+}
+
+/// A Graphviz digraph named `name` with one node per subterm, numbered in pre-order.
+pub fn to_dot<T: TermLike>(t: &T, name: &str) -> String {
+    let mut out = format!("digraph {} {{\n", name);
+    let mut next_id = 1;
+    let mut stack = vec![(0, t)];
+    while let Some((id, t)) = stack.pop() {
+        writeln!(out, "    n{} [label={:?}];", id, t.label()).unwrap();
+        for child in t.children() {
+            writeln!(out, "    n{} -> n{};", id, next_id).unwrap();
+            stack.push((next_id, child));
+            next_id += 1;
+        }
+    }
+    out.push('}');
+    out
+}
+
+/// The subterm reached by following the child indices in `path`.
+pub fn get<'a, T: TermLike>(t: &'a T, path: &[usize]) -> Option<&'a T> {
+    path.iter()
+        .try_fold(t, |t, &i| t.children().get(i).copied())
+}
+
+/// `t` with the subterm at `path` replaced by `with`, or `Err(t)` unchanged if `path`
+/// leads nowhere.
+pub fn replace<T: TermLike>(mut t: T, path: &[usize], with: T) -> Result<T, T> {
+    if get(&t, path).is_none() {
+        return Err(t);
+    }
+    let mut slot = &mut t;
+    for &i in path {
+        // `get` already checked that the whole path exists.
+        slot = slot.children_mut().swap_remove(i);
+    }
+    *slot = with;
+    Ok(t)
+}
+
+/// Generates a test module checking that the shared utilities and `TermLike` methods
+/// agree on `$ty`, for each term in the `samples` expression (a `Vec<$ty>`).
+#[macro_export]
+macro_rules! term_like_tests {
+    ($name:ident, $ty:ty, $samples:expr) => {
+        #[cfg(test)]
+        mod $name {
+            use $crate::TermLike;
+
+            use super::*;
+
+            fn samples() -> Vec<$ty> {
+                $samples
+            }
+
+            #[test]
+            fn test_metrics() {
+                for t in samples() {
+                    let children = t.children();
+                    let sizes: usize = children.iter().map(|c| $crate::size(*c)).sum();
+                    assert_eq!($crate::size(&t), 1 + sizes);
+                    let depth = children.iter().map(|c| $crate::depth(*c)).max();
+                    assert_eq!($crate::depth(&t), 1 + depth.unwrap_or(0));
+                    let counts = $crate::histogram(&t);
+                    assert_eq!(counts.values().sum::<usize>(), $crate::size(&t));
+                    assert!(counts[&t.label()] >= 1);
+                    let dot = $crate::to_dot(&t, "T");
+                    assert_eq!(dot.matches("->").count() + 1, $crate::size(&t));
+                }
+            }
+
+            #[test]
+            fn test_paths() {
+                for t in samples() {
+                    assert!(std::ptr::eq($crate::get(&t, &[]).unwrap(), &t));
+                    let arity = t.children().len();
+                    assert!($crate::get(&t, &[arity]).is_none());
+                    for i in 0..arity {
+                        let child = $crate::get(&t, &[i]).unwrap();
+                        assert!(std::ptr::eq(child, t.children()[i]));
+                    }
+                    let copy = <$ty>::clone(&t);
+                    let replaced = $crate::replace(copy, &[arity], <$ty>::clone(&t));
+                    assert!(replaced.is_err_and(|same| same == t));
+                    if arity > 0 {
+                        let leaf = <$ty>::clone($crate::get(&t, &[arity - 1]).unwrap());
+                        let replaced = $crate::replace(t.clone(), &[0], leaf.clone()).unwrap();
+                        assert!($crate::get(&replaced, &[0]) == Some(&leaf));
+                        assert_eq!(replaced.children().len(), arity);
+                    }
+                }
+            }
+
+            #[test]
+            fn test_parts_round_trip() {
+                for t in samples() {
+                    let children = t.children().into_iter().cloned().collect();
+                    let rebuilt = <$ty>::from_parts(&t.label(), children).unwrap();
+                    assert_eq!(rebuilt.label(), t.label());
+                    assert!(rebuilt.children() == t.children());
+                    assert!(<$ty>::from_parts("no such constructor", vec![]).is_none());
+                    let mut copy = t.clone();
+                    let children: Vec<$ty> =
+                        copy.children_mut().into_iter().map(|c| c.clone()).collect();
+                    assert!(children.iter().eq(t.children()));
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq)]
+enum Tree {
+    Leaf(u8),
+    Node(Vec<Tree>),
+}
+
+#[cfg(test)]
+impl TermLike for Tree {
+    fn children(&self) -> Vec<&Self> {
+        match self {
+            Tree::Leaf(_) => vec![],
+            Tree::Node(children) => children.iter().collect(),
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Self> {
+        match self {
+            Tree::Leaf(_) => vec![],
+            Tree::Node(children) => children.iter_mut().collect(),
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            Tree::Leaf(n) => n.to_string(),
+            Tree::Node(_) => "node".to_owned(),
+        }
+    }
+
+    fn from_parts(label: &str, children: Vec<Self>) -> Option<Self> {
+        match label {
+            "node" => Some(Tree::Node(children)),
+            _ if children.is_empty() => label.parse().ok().map(Tree::Leaf),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+fn tree(children: Vec<Tree>) -> Tree {
+    Tree::Node(children)
+}
+
+term_like_tests!(
+    tree_matrix,
+    Tree,
+    vec![
+        Tree::Leaf(1),
+        tree(vec![]),
+        tree(vec![
+            Tree::Leaf(1),
+            tree(vec![Tree::Leaf(2), Tree::Leaf(3)])
+        ]),
+    ]
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utilities() {
+        let t = tree(vec![
+            Tree::Leaf(1),
+            tree(vec![Tree::Leaf(2), Tree::Leaf(1)]),
+        ]);
+        assert_eq!(size(&t), 5);
+        assert_eq!(depth(&t), 3);
+        assert_eq!(
+            histogram(&t),
+            BTreeMap::from([
+                ("1".to_owned(), 2),
+                ("2".to_owned(), 1),
+                ("node".to_owned(), 2)
+            ])
+        );
+        assert_eq!(get(&t, &[1, 0]), Some(&Tree::Leaf(2)));
+        assert_eq!(
+            replace(t.clone(), &[1, 1], Tree::Leaf(7)).unwrap(),
+            tree(vec![
+                Tree::Leaf(1),
+                tree(vec![Tree::Leaf(2), Tree::Leaf(7)])
+            ])
+        );
+        assert_eq!(replace(t.clone(), &[0, 0], Tree::Leaf(7)), Err(t.clone()));
+        assert_eq!(
+            to_dot(&tree(vec![Tree::Leaf(1)]), "T"),
+            "digraph T {\n    n0 [label=\"node\"];\n    n0 -> n1;\n    n1 [label=\"1\"];\n}"
+        );
+
+        let deep = (0..100_000).fold(Tree::Leaf(0), |t, _| tree(vec![t]));
+        assert_eq!(depth(&deep), 100_001);
+        let path = vec![0; 100_000];
+        let deep = replace(deep, &path, Tree::Leaf(1)).unwrap();
+        assert_eq!(get(&deep, &path), Some(&Tree::Leaf(1)));
+        // Dropping it would recurse.
+        std::mem::forget(deep);
+    }
+}
@@ -0,0 +1,374 @@
+//! A language-independent record of an evaluation, and a standalone HTML viewer
+//! for it. Terms are kept both in their language's JSON encoding and as printed
+//! text, so the viewer never needs to know the language.
+
+use std::fmt::Write;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use thiserror::Error;
+
+pub const FORMAT: &str = "tapl-trace/1";
+
+/// How deeply a trace file may nest. Terms as deep as any language accepts fit with room
+/// to spare.
+pub const MAX_JSON_DEPTH: usize = 1 << 14;
+
+/// A whole-program term, as its language encodes and prints it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub term: Value,
+    pub text: String,
+}
+
+/// `rule` rewrote the subterm at child path `redex` of the previous term, which
+/// occupies bytes `redex_span` of its text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    pub rule: String,
+    pub redex: Vec<usize>,
+    pub redex_span: Option<(usize, usize)>,
+    pub after: Snapshot,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    pub language: String,
+    pub initial: Snapshot,
+    pub steps: Vec<Step>,
+    /// The final value, or why evaluation got stuck.
+    pub outcome: Result<Snapshot, String>,
+}
+
+#[derive(Error, Debug, PartialEq)]
+#[error("trace field `{0}` is missing or malformed")]
+pub struct SchemaError(pub &'static str);
+
+#[derive(Error, Debug)]
+pub enum ReadError {
+    #[error("invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("JSON nests deeper than {MAX_JSON_DEPTH} levels")]
+    TooDeep,
+    #[error(transparent)]
+    Schema(#[from] SchemaError),
+}
+
+// The deepest nesting of arrays and objects in `src`, ignoring brackets inside strings.
+fn json_depth(src: &str) -> usize {
+    let (mut depth, mut max) = (0usize, 0);
+    let (mut in_string, mut escaped) = (false, false);
+    for b in src.bytes() {
+        match b {
+            _ if escaped => escaped = false,
+            b'\\' if in_string => escaped = true,
+            b'"' => in_string = !in_string,
+            _ if in_string => {}
+            b'[' | b'{' => {
+                depth += 1;
+                max = max.max(depth);
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    max
+}
+
+fn snapshot_json(s: &Snapshot) -> Value {
+    json!({ "term": s.term, "text": s.text })
+}
+
+fn snapshot(v: &Value, field: &'static str) -> Result<Snapshot, SchemaError> {
+    let text = v
+        .get("text")
+        .and_then(Value::as_str)
+        .ok_or(SchemaError(field))?;
+    Ok(Snapshot {
+        term: v.get("term").ok_or(SchemaError(field))?.clone(),
+        text: text.to_owned(),
+    })
+}
+
+fn step(v: &Value) -> Result<Step, SchemaError> {
+    let rule = v
+        .get("rule")
+        .and_then(Value::as_str)
+        .ok_or(SchemaError("rule"))?;
+    let redex = v
+        .get("redex")
+        .and_then(Value::as_array)
+        .and_then(|path| path.iter().map(|i| Some(i.as_u64()? as usize)).collect())
+        .ok_or(SchemaError("redex"))?;
+    let redex_span = match v.get("redex_span") {
+        None | Some(Value::Null) => None,
+        Some(span) => {
+            let bound = |i| Some(span.get(i)?.as_u64()? as usize);
+            Some(bound(0).zip(bound(1)).ok_or(SchemaError("redex_span"))?)
+        }
+    };
+    Ok(Step {
+        rule: rule.to_owned(),
+        redex,
+        redex_span,
+        after: snapshot(v.get("after").ok_or(SchemaError("after"))?, "after")?,
+    })
+}
+
+impl Trace {
+    pub fn to_json(&self) -> Value {
+        let steps: Vec<Value> = self
+            .steps
+            .iter()
+            .map(|step| {
+                json!({
+                    "rule": step.rule,
+                    "redex": step.redex,
+                    "redex_span": step.redex_span.map(|(start, end)| [start, end]),
+                    "after": snapshot_json(&step.after),
+                })
+            })
+            .collect();
+        let (result, error) = match &self.outcome {
+            Ok(result) => (snapshot_json(result), Value::Null),
+            Err(error) => (Value::Null, json!(error)),
+        };
+        json!({
+            "format": FORMAT,
+            "language": self.language,
+            "initial": snapshot_json(&self.initial),
+            "steps": steps,
+            "result": result,
+            "error": error,
+            "stats": { "steps": self.steps.len() },
+        })
+    }
+
+    /// Reads a trace back, checking it against the schema `to_json` writes.
+    pub fn from_json(v: &Value) -> Result<Trace, SchemaError> {
+        if v.get("format").and_then(Value::as_str) != Some(FORMAT) {
+            return Err(SchemaError("format"));
+        }
+        let language = v.get("language").and_then(Value::as_str);
+        let steps = v.get("steps").and_then(Value::as_array);
+        let outcome = match (v.get("result"), v.get("error").and_then(Value::as_str)) {
+            (Some(Value::Null) | None, Some(error)) => Err(error.to_owned()),
+            (Some(result), None) => Ok(snapshot(result, "result")?),
+            _ => return Err(SchemaError("result")),
+        };
+        Ok(Trace {
+            language: language.ok_or(SchemaError("language"))?.to_owned(),
+            initial: snapshot(v.get("initial").ok_or(SchemaError("initial"))?, "initial")?,
+            steps: steps
+                .ok_or(SchemaError("steps"))?
+                .iter()
+                .map(step)
+                .collect::<Result<_, _>>()?,
+            outcome,
+        })
+    }
+
+    /// Reads a trace file. Unlike `serde_json::from_str`, this accepts terms nested deeper
+    /// than 128 levels. It recurses once per level, up to `MAX_JSON_DEPTH`, so run it on a
+    /// thread with a stack to match.
+    pub fn parse(src: &str) -> Result<Trace, ReadError> {
+        if json_depth(src) > MAX_JSON_DEPTH {
+            return Err(ReadError::TooDeep);
+        }
+        let mut de = serde_json::Deserializer::from_str(src);
+        de.disable_recursion_limit();
+        let v = Value::deserialize(&mut de)?;
+        de.end()?;
+        Ok(Trace::from_json(&v)?)
+    }
+}
+
+fn escape(out: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+}
+
+// `text` with the byte range `span` wrapped in <mark>, if it is a valid range.
+fn marked(out: &mut String, text: &str, span: Option<(usize, usize)>) {
+    match span.filter(|&(start, end)| text.get(start..end).is_some()) {
+        Some((start, end)) => {
+            escape(out, &text[..start]);
+            out.push_str("<mark>");
+            escape(out, &text[start..end]);
+            out.push_str("</mark>");
+            escape(out, &text[end..]);
+        }
+        None => escape(out, text),
+    }
+}
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+pre { background: #f4f4f4; padding: 0.5em; white-space: pre-wrap; }
+mark { background: #ffe08a; }
+.step { display: none; }
+.step.current { display: block; }
+.rule { font-weight: bold; }
+.error { color: #b00020; }";
+
+const SCRIPT: &str = "const slider = document.getElementById('slider');
+const steps = document.querySelectorAll('.step');
+function show() {
+  steps.forEach((step, i) => step.classList.toggle('current', i + 1 == slider.value));
+  document.getElementById('position').textContent = slider.value + ' / ' + steps.length;
+}
+if (slider) { slider.addEventListener('input', show); show(); }";
+
+/// A self-contained page stepping through `trace` with a slider, highlighting
+/// each step's redex in the term it rewrote.
+pub fn to_html(trace: &Trace) -> String {
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str("<title>");
+    escape(&mut out, &trace.language);
+    writeln!(
+        out,
+        " trace</title>\n<style>\n{}\n</style>\n</head>\n<body>",
+        STYLE
+    )
+    .unwrap();
+    out.push_str("<h1>");
+    escape(&mut out, &trace.language);
+    out.push_str(" trace</h1>\n<h2>Initial term</h2>\n<pre>");
+    escape(&mut out, &trace.initial.text);
+    out.push_str("</pre>\n");
+    if !trace.steps.is_empty() {
+        writeln!(
+            out,
+            "<p><input type=\"range\" id=\"slider\" min=\"1\" max=\"{}\" value=\"1\"> \
+             step <span id=\"position\"></span></p>",
+            trace.steps.len()
+        )
+        .unwrap();
+    }
+    let mut before = &trace.initial.text;
+    for (i, step) in trace.steps.iter().enumerate() {
+        write!(
+            out,
+            "<div class=\"step\" data-step=\"{}\">\n<p class=\"rule\">",
+            i + 1
+        )
+        .unwrap();
+        escape(&mut out, &step.rule);
+        out.push_str("</p>\n<pre>");
+        marked(&mut out, before, step.redex_span);
+        out.push_str("</pre>\n<pre>");
+        escape(&mut out, &step.after.text);
+        out.push_str("</pre>\n</div>\n");
+        before = &step.after.text;
+    }
+    match &trace.outcome {
+        Ok(result) => {
+            out.push_str("<h2>Result</h2>\n<pre class=\"result\">");
+            escape(&mut out, &result.text);
+        }
+        Err(error) => {
+            out.push_str("<h2>Stuck</h2>\n<pre class=\"error\">");
+            escape(&mut out, error);
+        }
+    }
+    writeln!(
+        out,
+        "</pre>\n<script>\n{}\n</script>\n</body>\n</html>",
+        SCRIPT
+    )
+    .unwrap();
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> Snapshot {
+        Snapshot {
+            term: json!(s),
+            text: s.to_owned(),
+        }
+    }
+
+    fn sample() -> Trace {
+        Trace {
+            language: "toy".to_owned(),
+            initial: text("a <b> c"),
+            steps: vec![
+                Step {
+                    rule: "R-One".to_owned(),
+                    redex: vec![1],
+                    redex_span: Some((2, 5)),
+                    after: text("a d c"),
+                },
+                Step {
+                    rule: "R-Two".to_owned(),
+                    redex: vec![],
+                    redex_span: None,
+                    after: text("e"),
+                },
+            ],
+            outcome: Ok(text("e")),
+        }
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let trace = sample();
+        let v = trace.to_json();
+        assert_eq!(v["stats"]["steps"], 2);
+        assert_eq!(Trace::from_json(&v), Ok(trace));
+
+        let stuck = Trace {
+            outcome: Err("no rule applies".to_owned()),
+            ..sample()
+        };
+        assert_eq!(Trace::from_json(&stuck.to_json()), Ok(stuck));
+    }
+
+    #[test]
+    fn test_schema_errors() {
+        let mut v = sample().to_json();
+        v["steps"][0]["redex"] = json!("0");
+        assert_eq!(Trace::from_json(&v), Err(SchemaError("redex")));
+        v["format"] = json!("other/1");
+        assert_eq!(Trace::from_json(&v), Err(SchemaError("format")));
+        assert_eq!(Trace::from_json(&json!([])), Err(SchemaError("format")));
+    }
+
+    #[test]
+    fn test_parse() {
+        let deep = (0..200).fold(json!("x"), |v, _| json!({ "S": v }));
+        let trace = Trace {
+            initial: Snapshot {
+                term: deep,
+                text: "[{\"".to_owned(),
+            },
+            ..sample()
+        };
+        let src = trace.to_json().to_string();
+        assert!(serde_json::from_str::<Value>(&src).is_err());
+        assert_eq!(Trace::parse(&src).unwrap(), trace);
+        assert!(matches!(Trace::parse("[1] 2"), Err(ReadError::Json(_))));
+        assert!(matches!(
+            Trace::parse(&"[".repeat(MAX_JSON_DEPTH + 1)),
+            Err(ReadError::TooDeep)
+        ));
+    }
+
+    #[test]
+    fn test_html() {
+        let html = to_html(&sample());
+        assert_eq!(html.matches("class=\"step\"").count(), 2);
+        assert!(html.contains("<pre>a <mark>&lt;b&gt;</mark> c</pre>"));
+        assert!(html.contains("max=\"2\""));
+        assert!(html.ends_with("</html>\n"));
+    }
+}
@@ -0,0 +1,36 @@
+use std::process::ExitCode;
+
+use tapl_trace::{to_html, Trace};
+
+// Room for `Trace::parse` at `MAX_JSON_DEPTH`, even in a debug build.
+const STACK: usize = 64 << 20;
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [path] = args.as_slice() else {
+        eprintln!("Usage: tapl-trace <trace.json>");
+        return ExitCode::from(2);
+    };
+    let src = match std::fs::read_to_string(path) {
+        Ok(src) => src,
+        Err(e) => {
+            eprintln!("error: {}: {}", path, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    // Deep terms are dropped on the same thread that read them, for the same reason.
+    let worker = std::thread::Builder::new()
+        .stack_size(STACK)
+        .spawn(move || Trace::parse(&src).map(|trace| to_html(&trace)))
+        .expect("failed to spawn the reader thread");
+    match worker.join().expect("the reader thread panicked") {
+        Ok(html) => {
+            print!("{}", html);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {}: {}", path, e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::process::ExitCode;
+
+use arith::output::Format;
+use tapl_diagnostics::ColorChoice;
+
+/// Flags accepted before the chapter name and forwarded to whichever chapter runs, unless
+/// the chapter's own arguments set the same flag.
+#[derive(Default)]
+struct Global {
+    output: Option<Format>,
+    color: Option<ColorChoice>,
+}
+
+const CHAPTERS: &[(&str, &str)] = &[
+    ("arith", "untyped arithmetic expressions (chapter 3)"),
+    ("typedarith", "typed arithmetic expressions (chapter 8)"),
+];
+
+const USAGE: &str = "Usage: tapl [--output <format>] [--color <when>] <chapter> [<chapter options>]
+       tapl --list
+
+Run `tapl <chapter> --help` for the options of a chapter.";
+
+fn run_chapter(global: &Global, chapter: &str, args: Vec<String>) -> Option<ExitCode> {
+    let given = |flag: &str| args.iter().any(|arg| arg == flag);
+    let output = global.output.filter(|_| !given("--output"));
+    let color = global.color.filter(|_| !given("--color"));
+    match chapter {
+        "arith" => {
+            let mut options = arith::cli::Options::parse(args.into_iter())?;
+            options.output = output.unwrap_or(options.output);
+            options.color = color.unwrap_or(options.color);
+            Some(arith::cli::run(&options))
+        }
+        "typedarith" => {
+            let mut options = typed_arith::cli::Options::parse(args.into_iter())?;
+            options.output = output.unwrap_or(options.output);
+            options.color = color.unwrap_or(options.color);
+            Some(typed_arith::cli::run(&options))
+        }
+        _ => None,
+    }
+}
+
+fn chapter_usage(chapter: &str) -> &'static str {
+    match chapter {
+        "arith" => arith::cli::USAGE,
+        "typedarith" => typed_arith::cli::USAGE,
+        _ => USAGE,
+    }
+}
+
+fn main() -> ExitCode {
+    let mut global = Global::default();
+    let mut args = std::env::args().skip(1);
+    let chapter = loop {
+        let parsed = match args.next().as_deref() {
+            Some("--list") => {
+                for (name, description) in CHAPTERS {
+                    println!("{:<12}{}", name, description);
+                }
+                return ExitCode::SUCCESS;
+            }
+            Some("--output") => args
+                .next()
+                .and_then(|v| v.parse().ok())
+                .map(|v| global.output = Some(v)),
+            Some("--color") => args
+                .next()
+                .and_then(|v| v.parse().ok())
+                .map(|v| global.color = Some(v)),
+            Some(chapter) if !chapter.starts_with('-') => break chapter.to_owned(),
+            _ => None,
+        };
+        if parsed.is_none() {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    run_chapter(&global, &chapter, args.collect()).unwrap_or_else(|| {
+        eprintln!("{}", chapter_usage(&chapter));
+        ExitCode::from(2)
+    })
+}
@@ -0,0 +1,63 @@
+use std::process::{Command, Output};
+
+fn tapl(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_tapl"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_list() {
+    let output = tapl(&["--list"]);
+    assert!(output.status.success());
+    let chapters: Vec<_> = stdout(&output)
+        .lines()
+        .map(|line| line.split_whitespace().next().unwrap().to_owned())
+        .collect();
+    assert_eq!(chapters, ["arith", "typedarith"]);
+}
+
+#[test]
+fn test_arith_expr() {
+    let output = tapl(&["arith", "--expr", "iszero 0"]);
+    assert!(output.status.success());
+    assert!(stdout(&output).ends_with("Output: true\n"));
+}
+
+#[test]
+fn test_global_output() {
+    let output = tapl(&["--output", "sexpr", "arith", "--expr", "succ pred 0"]);
+    assert!(stdout(&output).starts_with("Input: (succ (pred 0))\n"));
+}
+
+#[test]
+fn test_chapter_output_wins() {
+    let args = [
+        "--output", "sexpr", "arith", "--expr", "0", "--output", "json",
+    ];
+    let output = tapl(&args);
+    assert!(stdout(&output).starts_with("Input: \"Zero\"\n"));
+}
+
+#[test]
+fn test_typedarith_expr() {
+    let output = tapl(&["typedarith", "--expr", "if iszero 0 then 1 else 0"]);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("Type: Nat\n"));
+
+    let output = tapl(&["--color", "never", "typedarith", "--expr", "succ true"]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("error: succ expects Nat, found Bool\n --> <expr>:1:6"));
+}
+
+#[test]
+fn test_unknown_chapter() {
+    assert_eq!(tapl(&["untyped"]).status.code(), Some(2));
+    assert_eq!(tapl(&["arith", "--bogus"]).status.code(), Some(2));
+}
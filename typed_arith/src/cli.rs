@@ -0,0 +1,111 @@
+//! The `typed_arith` command line, exposed so that other drivers can embed it.
+
+use std::{fmt::Write, io::IsTerminal, process::ExitCode};
+
+use arith::{
+    eval_ast,
+    explain::derive,
+    output::{render, Format},
+    surface::desugar,
+    try_parse_surface_spanned,
+};
+use tapl_diagnostics::{ColorChoice, Diagnostic, Renderer, SourceFile};
+
+use crate::{type_of_with, TypeOptions};
+
+#[derive(Default)]
+pub struct Options {
+    /// Check this term instead of a line read from stdin.
+    pub expr: Option<String>,
+    pub output: Format,
+    pub color: ColorChoice,
+    pub explain_rule: bool,
+    pub typing: TypeOptions,
+}
+
+impl Options {
+    pub fn parse(args: impl Iterator<Item = String>) -> Option<Options> {
+        let mut options = Options::default();
+        let mut args = args;
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--expr" => options.expr = Some(args.next()?),
+                "--lenient" => options.typing.lenient = true,
+                "--explain-rule" => options.explain_rule = true,
+                "--output" => options.output = args.next()?.parse().ok()?,
+                "--color" => options.color = args.next()?.parse().ok()?,
+                _ => return None,
+            }
+        }
+        Some(options)
+    }
+}
+
+pub const USAGE: &str =
+    "Usage: typed_arith [--expr <term>] [--lenient] [--explain-rule] [--output <format>]
+                   [--color <when>]";
+
+// Writes what checking and evaluating `input` prints to `out`, leaving any error to the
+// caller.
+fn run_input(options: &Options, input: &str, out: &mut String) -> Result<(), Diagnostic> {
+    let (term, spans) = try_parse_surface_spanned(input).map_err(|e| e.to_diagnostic())?;
+    let ty = type_of_with(&term, &options.typing).map_err(|e| e.to_diagnostic(&spans))?;
+    let term = desugar(term);
+    writeln!(out, "Input: {}", render(&term, options.output)).unwrap();
+    writeln!(out, "Type: {}", ty).unwrap();
+    let output = if options.explain_rule {
+        let derivation = derive(term).map_err(|e| e.to_diagnostic())?;
+        for step in derivation.steps() {
+            writeln!(out, "{}: {} ⇓ {}", step.rule, step.term, step.value).unwrap();
+        }
+        derivation.value
+    } else {
+        eval_ast(term).map_err(|e| e.to_diagnostic())?
+    };
+    writeln!(out, "Output: {}", render(&output, options.output)).unwrap();
+    Ok(())
+}
+
+/// Checks and evaluates `input` as `typed_arith --expr` would, returning everything it
+/// prints: its output, then any error, rendered without color against a file called `name`.
+pub fn transcript(options: &Options, name: &str, input: &str) -> String {
+    let mut out = String::new();
+    if let Err(diagnostic) = run_input(options, input, &mut out) {
+        let file = SourceFile::new(name, input);
+        writeln!(out, "{}", Renderer::plain().render(&diagnostic, &file)).unwrap();
+    }
+    out
+}
+
+/// Typechecks then evaluates the input, rendering any error to stderr. Everything
+/// happens inside `with_stack`, so any term the parser accepts can be run.
+pub fn run(options: &Options) -> ExitCode {
+    arith::with_stack(|| run_here(options))
+}
+
+fn run_here(options: &Options) -> ExitCode {
+    let (name, input) = match &options.expr {
+        Some(expr) => ("<expr>", expr.clone()),
+        None => {
+            let mut buf = String::new();
+            std::io::stdin()
+                .read_line(&mut buf)
+                .expect("Failed to read input");
+            ("<stdin>", buf.trim_end().to_owned())
+        }
+    };
+    let mut out = String::new();
+    let result = run_input(options, &input, &mut out);
+    print!("{}", out);
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(diagnostic) => {
+            let renderer = options.color.renderer(std::io::stderr().is_terminal());
+            eprintln!(
+                "{}",
+                renderer.render(&diagnostic, &SourceFile::new(name, input))
+            );
+            ExitCode::FAILURE
+        }
+    }
+}
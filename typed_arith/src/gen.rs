@@ -0,0 +1,78 @@
+use arith::AST;
+use tapl_gen::Gen;
+
+use crate::Ty;
+
+fn boxed(v: AST) -> Box<AST> {
+    Box::new(v)
+}
+
+/// Generates a random term of type `ty` with `arith_size` at most `g.size()`. Every term
+/// has the lenient type `Top`, so for that a `Nat` or `Bool` term is chosen at random.
+pub fn well_typed(g: &mut Gen, ty: Ty) -> AST {
+    let if_then_else = |g: &mut Gen| {
+        if g.size() < 4 {
+            return None;
+        }
+        let sizes = g.split(3);
+        let cond = g.resize(sizes[0], |g| well_typed(g, Ty::Bool));
+        let then = g.resize(sizes[1], |g| well_typed(g, ty));
+        let els = g.resize(sizes[2], |g| well_typed(g, ty));
+        Some(AST::IfThenElse(boxed(cond), boxed(then), boxed(els)))
+    };
+    let nat = |g: &mut Gen| {
+        let size = g.size() - 1;
+        boxed(g.resize(size, |g| well_typed(g, Ty::Nat)))
+    };
+    match ty {
+        Ty::Nat => g.sized(
+            &[(1, &|_| AST::Zero)],
+            &[
+                (2, &|g| AST::Succ(nat(g))),
+                (2, &|g| AST::Pred(nat(g))),
+                (1, &|g| if_then_else(g).unwrap_or_else(|| AST::Succ(nat(g)))),
+            ],
+        ),
+        Ty::Bool => g.sized(
+            &[(1, &|_| AST::True), (1, &|_| AST::False)],
+            &[
+                (2, &|g| AST::IsZero(nat(g))),
+                (1, &|g| {
+                    if_then_else(g).unwrap_or_else(|| AST::IsZero(nat(g)))
+                }),
+            ],
+        ),
+        Ty::Top => {
+            let ty = [Ty::Nat, Ty::Bool][g.rng.below(2) as usize];
+            well_typed(g, ty)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arith::arith_size;
+
+    use super::*;
+    use crate::type_of_ast;
+
+    #[test]
+    fn test_well_typed_generation() {
+        let mut g = Gen::new(23, 15);
+        for ty in [Ty::Nat, Ty::Bool] {
+            let terms: Vec<AST> = (0..1000).map(|_| well_typed(&mut g, ty)).collect();
+            assert!(terms.iter().all(|v| type_of_ast(v) == Ok(ty)));
+            assert!(terms.iter().all(|v| arith_size(v) <= 15));
+            assert!(
+                terms
+                    .iter()
+                    .filter(|v| matches!(v, AST::IfThenElse(..)))
+                    .count()
+                    > 50
+            );
+            assert!(terms.iter().any(|v| arith_size(v) >= 12));
+        }
+        let mut nats = (0..1000).map(|_| well_typed(&mut g, Ty::Nat));
+        assert!(nats.any(|v| matches!(v, AST::Pred(_))));
+    }
+}
@@ -0,0 +1,272 @@
+use std::{fmt::Display, str::FromStr};
+
+use arith::{
+    surface::{SpanTree, SurfaceAST},
+    AST,
+};
+use tapl_diagnostics::Diagnostic;
+use thiserror::Error;
+
+pub mod cli;
+pub mod gen;
+
+/// The types of TAPL chapter 8, and `Top` above them both for the lenient `if`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ty {
+    Bool,
+    Nat,
+    Top,
+}
+
+/// The least common supertype of `a` and `b`.
+pub fn join(a: &Ty, b: &Ty) -> Ty {
+    if a == b {
+        *a
+    } else {
+        Ty::Top
+    }
+}
+
+impl Display for Ty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Ty::Bool => write!(f, "Bool"),
+            Ty::Nat => write!(f, "Nat"),
+            Ty::Top => write!(f, "Top"),
+        }
+    }
+}
+
+impl FromStr for Ty {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "Bool" => Ok(Ty::Bool),
+            "Nat" => Ok(Ty::Nat),
+            "Top" => Ok(Ty::Top),
+            _ => Err(format!("unknown type `{}`", s)),
+        }
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum TypeErrorKind {
+    #[error("{construct} expects {expected}, found {found}")]
+    Mismatch {
+        construct: &'static str,
+        expected: Ty,
+        found: Ty,
+    },
+    #[error("if branches have different types: {then} and {els}")]
+    BranchMismatch { then: Ty, els: Ty },
+}
+
+/// A typing failure, located by the child indices leading to the offending subterm.
+#[derive(Error, Debug, PartialEq)]
+#[error("{kind}")]
+pub struct TypeError {
+    pub path: Vec<usize>,
+    pub kind: TypeErrorKind,
+}
+
+impl TypeError {
+    fn within(mut self, child: usize) -> TypeError {
+        self.path.insert(0, child);
+        self
+    }
+
+    pub fn to_diagnostic(&self, spans: &SpanTree) -> Diagnostic {
+        let diagnostic = Diagnostic::error(self.to_string());
+        let label = match &self.kind {
+            TypeErrorKind::Mismatch { found, .. } => format!("this has type {}", found),
+            TypeErrorKind::BranchMismatch { els, .. } => format!("this has type {}", els),
+        };
+        match spans.get(&self.path) {
+            Some(span) => diagnostic.with_label(span, label),
+            None => diagnostic,
+        }
+    }
+}
+
+/// Knobs for typing exercises that relax the book's rules.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TypeOptions {
+    /// Give `if` the `join` of its branch types instead of requiring them to match.
+    pub lenient: bool,
+}
+
+fn expect(
+    v: &SurfaceAST,
+    child: usize,
+    construct: &'static str,
+    expected: Ty,
+    options: &TypeOptions,
+) -> Result<(), TypeError> {
+    let found = type_of_with(v, options).map_err(|e| e.within(child))?;
+    if found == expected {
+        Ok(())
+    } else {
+        Err(TypeError {
+            path: vec![child],
+            kind: TypeErrorKind::Mismatch {
+                construct,
+                expected,
+                found,
+            },
+        })
+    }
+}
+
+/// Types a surface term with the T-rules of figures 8-1 and 8-2; the
+/// connectives and numerals get the types of their desugarings.
+pub fn type_of(v: &SurfaceAST) -> Result<Ty, TypeError> {
+    type_of_with(v, &TypeOptions::default())
+}
+
+/// Like `type_of`, with `options` deciding how strict T-If is.
+pub fn type_of_with(v: &SurfaceAST, options: &TypeOptions) -> Result<Ty, TypeError> {
+    let expect = |v, child, construct, expected| expect(v, child, construct, expected, options);
+    match v {
+        SurfaceAST::True | SurfaceAST::False => Ok(Ty::Bool), // T-True, T-False
+        SurfaceAST::Zero | SurfaceAST::Numeral(_) => Ok(Ty::Nat), // T-Zero
+        SurfaceAST::Succ(v) => expect(v, 0, "succ", Ty::Nat).map(|_| Ty::Nat), // T-Succ
+        SurfaceAST::Pred(v) => expect(v, 0, "pred", Ty::Nat).map(|_| Ty::Nat), // T-Pred
+        SurfaceAST::IsZero(v) => expect(v, 0, "iszero", Ty::Nat).map(|_| Ty::Bool), // T-IsZero
+        SurfaceAST::Not(v) => expect(v, 0, "not", Ty::Bool).map(|_| Ty::Bool),
+        SurfaceAST::And(a, b) => {
+            expect(a, 0, "and", Ty::Bool)?;
+            expect(b, 1, "and", Ty::Bool).map(|_| Ty::Bool)
+        }
+        SurfaceAST::Or(a, b) => {
+            expect(a, 0, "or", Ty::Bool)?;
+            expect(b, 1, "or", Ty::Bool).map(|_| Ty::Bool)
+        }
+        SurfaceAST::IfThenElse(cond, then, els) => {
+            // T-If
+            expect(cond, 0, "if condition", Ty::Bool)?;
+            let then = type_of_with(then, options).map_err(|e| e.within(1))?;
+            let els = type_of_with(els, options).map_err(|e| e.within(2))?;
+            if options.lenient || then == els {
+                Ok(join(&then, &els))
+            } else {
+                Err(TypeError {
+                    path: vec![2],
+                    kind: TypeErrorKind::BranchMismatch { then, els },
+                })
+            }
+        }
+    }
+}
+
+/// Types a core term; the error path indexes the same children as `AST`.
+pub fn type_of_ast(v: &AST) -> Result<Ty, TypeError> {
+    type_of(&SurfaceAST::from(v.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use arith::{try_parse_surface, try_parse_surface_spanned};
+    use tapl_diagnostics::{Renderer, SourceFile};
+
+    use crate::{join, type_of, type_of_with, Ty, TypeError, TypeErrorKind, TypeOptions};
+
+    fn check(input: &str) -> Result<Ty, TypeError> {
+        type_of(&try_parse_surface(input).unwrap())
+    }
+
+    #[test]
+    fn test_well_typed() {
+        assert_eq!(check("if iszero 0 then succ 0 else 0"), Ok(Ty::Nat));
+        assert_eq!(check("iszero pred 3"), Ok(Ty::Bool));
+        assert_eq!(check("not true and (false or true)"), Ok(Ty::Bool));
+        assert_eq!(check("if true then false else true"), Ok(Ty::Bool));
+    }
+
+    #[test]
+    fn test_chapter_8_ill_typed() {
+        // The stuck terms quoted at the opening of chapter 8.
+        assert_eq!(
+            check("succ true"),
+            Err(TypeError {
+                path: vec![0],
+                kind: TypeErrorKind::Mismatch {
+                    construct: "succ",
+                    expected: Ty::Nat,
+                    found: Ty::Bool,
+                },
+            })
+        );
+        assert_eq!(
+            check("if 0 then 0 else 0"),
+            Err(TypeError {
+                path: vec![0],
+                kind: TypeErrorKind::Mismatch {
+                    construct: "if condition",
+                    expected: Ty::Bool,
+                    found: Ty::Nat,
+                },
+            })
+        );
+        // Evaluates to 0, but T-If rejects it anyway (section 8.2).
+        assert_eq!(
+            check("if true then 0 else false"),
+            Err(TypeError {
+                path: vec![2],
+                kind: TypeErrorKind::BranchMismatch {
+                    then: Ty::Nat,
+                    els: Ty::Bool,
+                },
+            })
+        );
+        assert!(check("iszero false").is_err());
+        assert!(check("pred iszero 0").is_err());
+    }
+
+    #[test]
+    fn test_lenient_join() {
+        assert_eq!(join(&Ty::Nat, &Ty::Nat), Ty::Nat);
+        assert_eq!(join(&Ty::Nat, &Ty::Bool), Ty::Top);
+        let lenient = |input| {
+            let options = TypeOptions { lenient: true };
+            type_of_with(&try_parse_surface(input).unwrap(), &options)
+        };
+        assert_eq!(lenient("if true then 0 else false"), Ok(Ty::Top));
+        assert!(check("if true then 0 else false").is_err());
+        assert_eq!(lenient("if false then 0 else succ 0"), Ok(Ty::Nat));
+        // Nothing consumes a `Top`, so the join does not let it through where Nat is due.
+        assert!(lenient("succ (if true then 0 else false)").is_err());
+    }
+
+    #[test]
+    fn test_ty_round_trip() {
+        for ty in [Ty::Bool, Ty::Nat, Ty::Top] {
+            assert_eq!(ty.to_string().parse(), Ok(ty));
+        }
+        assert_eq!(" Nat".parse::<Ty>(), Err("unknown type ` Nat`".to_owned()));
+    }
+
+    #[test]
+    fn test_error_path() {
+        let err = check("if true then succ (iszero 0) else 0").unwrap_err();
+        assert_eq!(err.path, vec![1, 0]);
+    }
+
+    #[test]
+    fn test_diagnostic_span() {
+        let input = "if true then succ (iszero 0) else 0";
+        let (term, spans) = try_parse_surface_spanned(input).unwrap();
+        let err = type_of(&term).unwrap_err();
+        let rendered = Renderer::plain().render(
+            &err.to_diagnostic(&spans),
+            &SourceFile::new("<stdin>", input),
+        );
+        assert_eq!(
+            rendered,
+            "error: succ expects Nat, found Bool
+ --> <stdin>:1:20
+  |
+1 | if true then succ (iszero 0) else 0
+  |                    ^^^^^^^^ this has type Bool"
+        );
+    }
+}
@@ -0,0 +1,11 @@
+use std::process::ExitCode;
+
+use typed_arith::cli::{run, Options, USAGE};
+
+fn main() -> ExitCode {
+    let Some(options) = Options::parse(std::env::args().skip(1)) else {
+        eprintln!("{}", USAGE);
+        return ExitCode::from(2);
+    };
+    run(&options)
+}
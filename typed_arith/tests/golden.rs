@@ -0,0 +1,14 @@
+use typed_arith::cli::{transcript, Options};
+
+#[test]
+fn test_golden() {
+    let options = Options {
+        explain_rule: true,
+        ..Options::default()
+    };
+    tapl_golden::check(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../fixtures/typed_arith"),
+        "txt",
+        |input| transcript(&options, "<fixture>", input),
+    );
+}
@@ -0,0 +1,47 @@
+//! Progress and preservation (TAPL theorems 8.3.2 and 8.3.3) as executable
+//! properties over arith's small-step relation: a well-typed term is a value or
+//! can take a step, and a step preserves its type.
+
+use arith::{eval1, eval_ast, gen::enumerate_arith, is_val, AST};
+use tapl_gen::Gen;
+use typed_arith::{gen::well_typed, type_of_ast, Ty};
+
+fn well_typed_terms() -> impl Iterator<Item = AST> {
+    let mut g = Gen::new(2023, 20);
+    let random: Vec<AST> = (0..2000)
+        .map(|i| well_typed(&mut g, [Ty::Nat, Ty::Bool][i % 2]))
+        .collect();
+    enumerate_arith(6)
+        .filter(|v| type_of_ast(v).is_ok())
+        .chain(random)
+}
+
+#[test]
+fn test_progress() {
+    let mut checked = 0;
+    for v in well_typed_terms() {
+        assert!(is_val(&v) || eval1(v.clone()).is_ok(), "{} is stuck", v);
+        checked += 1;
+    }
+    assert!(checked > 1000);
+}
+
+#[test]
+fn test_preservation() {
+    let mut stepped = 0;
+    for v in well_typed_terms() {
+        if let Ok(next) = eval1(v.clone()) {
+            assert_eq!(type_of_ast(&next), type_of_ast(&v), "{} → {}", v, next);
+            stepped += 1;
+        }
+    }
+    assert!(stepped > 1000);
+}
+
+#[test]
+fn test_ill_typed_terms_may_still_evaluate() {
+    // Typing is conservative: this term is rejected but evaluates fine.
+    let v = arith::try_parse("if true then 0 else false").unwrap();
+    assert!(type_of_ast(&v).is_err());
+    assert_eq!(eval_ast(v).unwrap(), AST::Zero);
+}